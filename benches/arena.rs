@@ -0,0 +1,46 @@
+extern crate theban_interval_tree;
+extern crate rand;
+extern crate time;
+extern crate memrange;
+
+use time::PreciseTime;
+use memrange::Range;
+use theban_interval_tree::{IntervalTree, ArenaIntervalTree};
+
+fn random_ranges(count: usize) -> Vec<Range> {
+    (0..count).map(|_| {
+        let offset = rand::random::<u64>()%(count as u64 * 16);
+        Range::new(offset, offset + rand::random::<u64>()%64)
+    }).collect()
+}
+
+fn main(){
+    let count = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(1_000_000);
+    let ranges = random_ranges(count);
+    let queries = random_ranges(100_000);
+    println!("{} entries, {} queries", count, queries.len());
+
+    let start = PreciseTime::now();
+    let mut boxed = IntervalTree::<usize>::new();
+    for (i, r) in ranges.iter().enumerate() { boxed.insert(*r, i) }
+    let inserted = PreciseTime::now();
+    let found = queries.iter().map(|q| boxed.range(q.min, q.max).count()).sum::<usize>();
+    let queried = PreciseTime::now();
+    for r in ranges.iter().take(count/2) { boxed.delete(*r) }
+    let deleted = PreciseTime::now();
+    println!("box:   insert {:>6}ms  query {:>6}ms  delete {:>6}ms  ({} results)",
+        start.to(inserted).num_milliseconds(), inserted.to(queried).num_milliseconds(),
+        queried.to(deleted).num_milliseconds(), found);
+
+    let start = PreciseTime::now();
+    let mut arena = ArenaIntervalTree::<usize>::new();
+    for (i, r) in ranges.iter().enumerate() { arena.insert(*r, i) }
+    let inserted = PreciseTime::now();
+    let found = queries.iter().map(|q| arena.range(q.min, q.max).count()).sum::<usize>();
+    let queried = PreciseTime::now();
+    for r in ranges.iter().take(count/2) { arena.delete(*r) }
+    let deleted = PreciseTime::now();
+    println!("arena: insert {:>6}ms  query {:>6}ms  delete {:>6}ms  ({} results)",
+        start.to(inserted).num_milliseconds(), inserted.to(queried).num_milliseconds(),
+        queried.to(deleted).num_milliseconds(), found);
+}
@@ -0,0 +1,51 @@
+extern crate theban_interval_tree;
+extern crate rand;
+extern crate time;
+extern crate memrange;
+
+use time::PreciseTime;
+use memrange::Range;
+use theban_interval_tree::{IntervalIndex, IntervalTree, FrozenIntervalTree, CenteredIntervalTree, NCList};
+
+/// short intervals that hardly overlap
+fn disjoint(count: usize) -> Vec<(Range, usize)> {
+    let mut t = IntervalTree::<usize>::new();
+    for i in 0..count {
+        let offset = rand::random::<u64>()%(count as u64 * 16);
+        t.insert(Range::new(offset, offset + rand::random::<u64>()%64), i);
+    }
+    t.iter().map(|(k, v)| (k, *v)).collect()
+}
+
+/// intervals of all sizes from 16 up to 2^16, so that many of them are nested
+fn nested(count: usize) -> Vec<(Range, usize)> {
+    let mut t = IntervalTree::<usize>::new();
+    for i in 0..count {
+        let len = 16u64 << (rand::random::<u32>()%13);
+        let offset = rand::random::<u64>()%(count as u64 * 16);
+        t.insert(Range::new(offset, offset + len), i);
+    }
+    t.iter().map(|(k, v)| (k, *v)).collect()
+}
+
+fn query<I: IntervalIndex<usize>>(name: &str, index: &I, queries: &[Range]) {
+    let start = PreciseTime::now();
+    let found = queries.iter().map(|q| index.range(q.min, q.max).count()).sum::<usize>();
+    let queried = PreciseTime::now();
+    println!("  {:<9} query {:>6}ms  ({} results)", name, start.to(queried).num_milliseconds(), found);
+}
+
+fn main(){
+    let count = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(1_000_000);
+    let queries = (0..10_000).map(|_| {
+        let offset = rand::random::<u64>()%(count as u64 * 16);
+        Range::new(offset, offset + 64)
+    }).collect::<Vec<_>>();
+    for &(name, ref entries) in &[("disjoint", disjoint(count)), ("nested", nested(count))] {
+        println!("{}: {} entries, {} queries", name, entries.len(), queries.len());
+        query("tree", &IntervalTree::<usize>::from_sorted(entries.clone()).unwrap(), &queries);
+        query("frozen", &FrozenIntervalTree::from_sorted(entries.clone()).unwrap(), &queries);
+        query("centered", &CenteredIntervalTree::from_sorted(entries.clone()).unwrap(), &queries);
+        query("nclist", &NCList::from_sorted(entries.clone()).unwrap(), &queries);
+    }
+}
@@ -0,0 +1,54 @@
+extern crate theban_interval_tree;
+extern crate rand;
+extern crate time;
+extern crate memrange;
+
+use time::PreciseTime;
+use memrange::Range;
+use theban_interval_tree::{IntervalTree, IntervalBTree};
+
+fn random_ranges(count: usize, span: u64) -> Vec<Range> {
+    (0..count).map(|_| {
+        let offset = rand::random::<u64>()%span;
+        Range::new(offset, offset + rand::random::<u64>()%64)
+    }).collect()
+}
+
+fn report(name: &str, start: PreciseTime, inserted: PreciseTime, queried: PreciseTime, deleted: PreciseTime, found: usize) {
+    println!("  {:<6} insert {:>7}ms  query {:>6}ms  delete {:>7}ms  ({} results)", name,
+        start.to(inserted).num_milliseconds(), inserted.to(queried).num_milliseconds(),
+        queried.to(deleted).num_milliseconds(), found);
+}
+
+/// Compares the AVL tree with the B-tree. The sizes can be given as arguments, the default stops
+/// at 10^7 entries because 10^8 entries need more than 10 GB of memory.
+fn main(){
+    let mut sizes = std::env::args().skip(1).filter_map(|s| s.parse().ok()).collect::<Vec<usize>>();
+    if sizes.is_empty() { sizes = vec![10_000, 100_000, 1_000_000, 10_000_000] }
+    for count in sizes {
+        let ranges = random_ranges(count, count as u64 * 16);
+        let queries = random_ranges(100_000, count as u64 * 16);
+        println!("{} entries, {} queries", count, queries.len());
+
+        let start = PreciseTime::now();
+        let mut avl = IntervalTree::<usize>::new();
+        for (i, r) in ranges.iter().enumerate() { avl.insert(*r, i) }
+        let inserted = PreciseTime::now();
+        let found = queries.iter().map(|q| avl.range(q.min, q.max).count()).sum::<usize>();
+        let queried = PreciseTime::now();
+        for r in ranges.iter().take(count/2) { avl.delete(*r) }
+        let deleted = PreciseTime::now();
+        report("avl", start, inserted, queried, deleted, found);
+        drop(avl);
+
+        let start = PreciseTime::now();
+        let mut btree = IntervalBTree::<usize>::new();
+        for (i, r) in ranges.iter().enumerate() { btree.insert(*r, i) }
+        let inserted = PreciseTime::now();
+        let found = queries.iter().map(|q| btree.range(q.min, q.max).count()).sum::<usize>();
+        let queried = PreciseTime::now();
+        for r in ranges.iter().take(count/2) { btree.delete(*r) }
+        let deleted = PreciseTime::now();
+        report("btree", start, inserted, queried, deleted, found);
+    }
+}
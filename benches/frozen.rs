@@ -0,0 +1,37 @@
+extern crate theban_interval_tree;
+extern crate rand;
+extern crate time;
+extern crate memrange;
+
+use time::PreciseTime;
+use memrange::Range;
+use theban_interval_tree::IntervalTree;
+
+fn random_ranges(count: usize) -> Vec<Range> {
+    (0..count).map(|_| {
+        let offset = rand::random::<u64>()%(count as u64 * 16);
+        Range::new(offset, offset + rand::random::<u64>()%64)
+    }).collect()
+}
+
+fn main(){
+    let count = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(1_000_000);
+    let ranges = random_ranges(count);
+    let queries = random_ranges(100_000);
+    println!("{} entries, {} queries", count, queries.len());
+
+    let mut boxed = IntervalTree::<usize>::new();
+    for (i, r) in ranges.iter().enumerate() { boxed.insert(*r, i) }
+    let start = PreciseTime::now();
+    let found = queries.iter().map(|q| boxed.range(q.min, q.max).count()).sum::<usize>();
+    let queried = PreciseTime::now();
+    println!("box:    query {:>6}ms  ({} results)", start.to(queried).num_milliseconds(), found);
+
+    let start = PreciseTime::now();
+    let frozen = boxed.freeze();
+    let frozen_at = PreciseTime::now();
+    let found = queries.iter().map(|q| frozen.count(q.min, q.max)).sum::<usize>();
+    let queried = PreciseTime::now();
+    println!("frozen: freeze {:>6}ms  query {:>6}ms  ({} results)",
+        start.to(frozen_at).num_milliseconds(), frozen_at.to(queried).num_milliseconds(), found);
+}
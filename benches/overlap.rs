@@ -0,0 +1,43 @@
+extern crate theban_interval_tree;
+extern crate rand;
+extern crate time;
+extern crate memrange;
+
+use time::PreciseTime;
+use memrange::Range;
+use theban_interval_tree::IntervalTree;
+
+fn random_ranges(count: usize, spread: u64) -> Vec<Range> {
+    (0..count).map(|_| {
+        let offset = rand::random::<u64>()%spread;
+        Range::new(offset, offset + rand::random::<u64>()%64)
+    }).collect()
+}
+
+fn main(){
+    let count = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(1_000_000);
+    let mut t = IntervalTree::<usize>::new();
+    for (i, r) in random_ranges(count, count as u64 * 16).iter().enumerate() { t.insert(*r, i) }
+    let queries = random_ranges(1_000_000, count as u64 * 16);
+    println!("{} entries, {} queries", count, queries.len());
+
+    let start = PreciseTime::now();
+    let found = queries.iter().map(|q| t.range(q.min, q.max).count()).sum::<usize>();
+    let end = PreciseTime::now();
+    println!("count  RangePairIter        {:>6}ms ({} results)", start.to(end).num_milliseconds(), found);
+
+    let start = PreciseTime::now();
+    let found = queries.iter().map(|q| t.count_overlapping(q.min..=q.max)).sum::<usize>();
+    let end = PreciseTime::now();
+    println!("count  count_overlapping    {:>6}ms ({} results)", start.to(end).num_milliseconds(), found);
+
+    let start = PreciseTime::now();
+    let found = queries.iter().filter(|q| t.range(q.min, q.max).next().is_some()).count();
+    let end = PreciseTime::now();
+    println!("any    RangePairIter        {:>6}ms ({} hits)", start.to(end).num_milliseconds(), found);
+
+    let start = PreciseTime::now();
+    let found = queries.iter().filter(|q| t.any_overlapping(q.min..=q.max)).count();
+    let end = PreciseTime::now();
+    println!("any    any_overlapping      {:>6}ms ({} hits)", start.to(end).num_milliseconds(), found);
+}
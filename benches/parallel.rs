@@ -0,0 +1,37 @@
+extern crate theban_interval_tree;
+extern crate rand;
+extern crate rayon;
+extern crate time;
+extern crate memrange;
+
+use rayon::prelude::*;
+use time::PreciseTime;
+use memrange::Range;
+use theban_interval_tree::IntervalTree;
+
+fn main(){
+    let count = std::env::args().nth(1).and_then(|s| s.parse().ok()).unwrap_or(10_000_000u64);
+    let entries = (0..count).map(|i| (Range::new(i * 4, i * 4 + rand::random::<u64>()%64), i)).collect::<Vec<_>>();
+    println!("{} entries on {} threads", count, rayon::current_num_threads());
+
+    let start = PreciseTime::now();
+    let t = IntervalTree::<u64>::from_sorted(entries.clone()).expect("sorted entries");
+    let end = PreciseTime::now();
+    println!("build  from_sorted          {:>6}ms", start.to(end).num_milliseconds());
+    drop(t);
+
+    let start = PreciseTime::now();
+    let t = IntervalTree::<u64>::par_from_sorted(entries).expect("sorted entries");
+    let end = PreciseTime::now();
+    println!("build  par_from_sorted      {:>6}ms", start.to(end).num_milliseconds());
+
+    let start = PreciseTime::now();
+    let sum = t.range(count, count * 3).map(|(_, v)| *v).sum::<u64>();
+    let end = PreciseTime::now();
+    println!("scan   range                {:>6}ms ({})", start.to(end).num_milliseconds(), sum);
+
+    let start = PreciseTime::now();
+    let sum = t.par_range(count..=count * 3).map(|(_, v)| *v).sum::<u64>();
+    let end = PreciseTime::now();
+    println!("scan   par_range            {:>6}ms ({})", start.to(end).num_milliseconds(), sum);
+}
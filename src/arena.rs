@@ -0,0 +1,284 @@
+use core::cmp::Ordering;
+use core::mem;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::node::{Node, NodeStore, Index, Indexed, Link, Slot, search, node_height};
+use ::iterators::NodeRangeIter;
+
+/// The nodes of `ArenaIntervalTree`, linked by their index in the slots of the tree.
+pub type ArenaNode<D> = Node<D, (), Indexed>;
+
+/// Stores node in the first slot of the free list starting at free, or gives it back if the list
+/// is empty. Returns the index of the slot.
+pub fn occupy<D>(slots: &mut [Slot<ArenaNode<D>>], free: &mut Option<Index>, node: ArenaNode<D>) -> Result<Index, ArenaNode<D>> {
+    let index = match *free {
+        Some(index) => index,
+        None => return Err(node),
+    };
+    match mem::replace(&mut slots[index.position()], Slot::Occupied(node)) {
+        Slot::Vacant(next) => *free = next,
+        Slot::Occupied(_) => panic!("Arena broken"),
+    }
+    Ok(index)
+}
+
+/// Moves the node out of the slot at index and puts the slot at the front of the free list.
+pub fn release<D>(slots: &mut [Slot<ArenaNode<D>>], free: &mut Option<Index>, index: Index) -> ArenaNode<D> {
+    match mem::replace(&mut slots[index.position()], Slot::Vacant(*free)) {
+        Slot::Occupied(node) => { *free = Some(index); node },
+        Slot::Vacant(_) => panic!("Arena broken"),
+    }
+}
+
+/// Returns the node behind root, if any.
+pub fn root_node<'a, D>(slots: &'a NodeStore<D, (), Indexed>, root: &'a Option<Index>) -> Option<&'a ArenaNode<D>> {
+    root.as_ref().map(|root| Indexed::get(slots, root))
+}
+
+/// Returns the index of the node stored under key in the tree given by root, if any.
+pub fn search_index<D>(slots: &NodeStore<D, (), Indexed>, mut root: Option<Index>, key: &Range) -> Option<Index> {
+    while let Some(index) = root {
+        let node = Indexed::get(slots, &index);
+        match node.key.cmp(key) {
+            Ordering::Equal => return Some(index),
+            Ordering::Less => root = node.right,
+            Ordering::Greater => root = node.left,
+        }
+    }
+    None
+}
+
+/// Returns the leftmost or the rightmost entry of the tree given by root.
+pub fn extreme_pair<'a, D>(slots: &'a NodeStore<D, (), Indexed>, root: Option<&'a ArenaNode<D>>, leftmost: bool) -> Option<(&'a Range, &'a D)> {
+    let mut node = root?;
+    loop {
+        let (left, right) = node.successors(slots);
+        match if leftmost { left } else { right } {
+            Some(next) => node = next,
+            None => return Some((&node.key, &node.data)),
+        }
+    }
+}
+
+/// An interval tree with the same interface as `IntervalTree`, but all nodes are stored in a
+/// single `Vec` and linked by u32 indices. Slots of deleted nodes are kept in a free list and reused
+/// by later inserts.
+#[derive(Debug)]
+pub struct ArenaIntervalTree<D> {
+    slots: Vec<Slot<ArenaNode<D>>>,
+    free: Option<Index>,
+    root: Option<Index>,
+    len: usize,
+}
+
+impl<D> Default for ArenaIntervalTree<D> {
+    fn default() -> ArenaIntervalTree<D> {
+        ArenaIntervalTree::new()
+    }
+}
+
+impl<D> ArenaIntervalTree<D> {
+
+/// This function will construct a new empty ArenaIntervalTree.
+/// # Examples
+/// ```
+/// extern crate theban_interval_tree;
+/// let mut t=theban_interval_tree::ArenaIntervalTree::<i32>::new();
+/// ```
+    pub fn new() -> ArenaIntervalTree<D> {
+        ArenaIntervalTree { slots: Vec::new(), free: None, root: None, len: 0 }
+    }
+
+/// This function will construct a new empty ArenaIntervalTree with room for `capacity` nodes.
+    pub fn with_capacity(capacity: usize) -> ArenaIntervalTree<D> {
+        ArenaIntervalTree { slots: Vec::with_capacity(capacity), free: None, root: None, len: 0 }
+    }
+
+    fn alloc(&mut self, node: ArenaNode<D>) -> Index {
+        match occupy(&mut self.slots, &mut self.free, node) {
+            Ok(index) => index,
+            Err(node) => {
+                assert!(self.slots.len() < u32::MAX as usize, "ArenaIntervalTree is full");
+                self.slots.push(Slot::Occupied(node));
+                Index::new(self.slots.len() - 1)
+            }
+        }
+    }
+
+    fn release(&mut self, index: Index) -> D {
+        release(&mut self.slots, &mut self.free, index).data
+    }
+
+    fn root(&self) -> Option<&ArenaNode<D>> {
+        root_node(&self.slots, &self.root)
+    }
+
+/// This function will insert the key,value pair into the tree, overwriting the old data if the key is allready
+/// part of the tree.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::ArenaIntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(2,2),25);
+/// assert_eq!(t.get(memrange::Range::new(2,2)), Some(&25));
+/// t.insert(memrange::Range::new(2,2),30);
+/// assert_eq!(t.get(memrange::Range::new(2,2)), Some(&30));
+/// ```
+    pub fn insert(&mut self, key: Range, data: D) {
+        let new = self.alloc(ArenaNode::new(key, data));
+        let (root, unlinked) = ArenaNode::insert(&mut self.slots, self.root, new);
+        self.root = Some(root);
+        match unlinked {
+            Some(index) => { self.release(index); },
+            None => self.len += 1,
+        }
+    }
+
+/// This function will remove the key,value pair from the tree, doing nothing if the key is not
+/// part of the tree.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::ArenaIntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(2,2),25);
+/// t.delete(memrange::Range::new(2,2));
+/// assert!(t.empty());
+/// ```
+    pub fn delete(&mut self, key: Range) {
+        self.remove(key);
+    }
+
+/// This function will remove the key,value pair from the tree and return the data, or None if the
+/// key is not part of the tree.
+    pub fn remove(&mut self, key: Range) -> Option<D> {
+        let (root, removed) = ArenaNode::remove(&mut self.slots, self.root, &key);
+        self.root = root;
+        let removed = removed?;
+        self.len -= 1;
+        Some(self.release(removed))
+    }
+
+/// This function will return the Some(data) stored under the given key or None if the key is not
+/// known.
+    pub fn get(&self, key: Range) -> Option<&D> {
+        self.root().and_then(|root| search(&self.slots[..], &key, root))
+    }
+
+/// This function will return the data stored under the given key or the default if the key is not
+/// known.
+    pub fn get_or<'a>(&'a self, key: Range, default: &'a D) -> &'a D {
+        self.get(key).unwrap_or(default)
+    }
+
+/// This function will return true if the tree contains the given key, false otherwise
+    pub fn contains(&self, key: Range) -> bool {
+        self.get(key).is_some()
+    }
+
+/// This function will return true if the tree is empty, false otherwise.
+    pub fn empty(&self) -> bool { self.root.is_none() }
+
+/// This function will return the number of key,value pairs in the tree.
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return true if the tree is empty, false otherwise (same as `empty`).
+    pub fn is_empty(&self) -> bool { self.empty() }
+
+/// This function will return the key/value pair with the smallest key in the tree, or None if the
+/// tree is empty.
+    pub fn min(&self) -> Option<(&Range, &D)> {
+        extreme_pair(&self.slots, self.root(), true)
+    }
+
+/// This function will return the key/value pair with the biggest key in the tree, or None if the
+/// tree is empty.
+    pub fn max(&self) -> Option<(&Range, &D)> {
+        extreme_pair(&self.slots, self.root(), false)
+    }
+
+/// This function will return the hieght of the tree. An empty tree hash height 0, one with only
+/// one elemente has height 1 etc.
+    pub fn height(&self) -> usize {
+        node_height(self.root()) as usize
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs in the tree.
+    pub fn iter(&self) -> ArenaRangePairIter<'_, D> {
+        self.range(0, 0xffff_ffff_ffff_ffff)
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs between the two
+/// bounds.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::ArenaIntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(3,8),1);
+/// t.insert(memrange::Range::new(12,36),2);
+/// assert_eq!(t.range(9,14).map(|(k,_)| k.min).collect::<Vec<u64>>(), vec![12]);
+/// ```
+    pub fn range(&self, min: u64, max: u64) -> ArenaRangePairIter<'_, D> {
+        ArenaRangePairIter { inner: NodeRangeIter::from_root(&self.slots[..], self.root(), min, max) }
+    }
+}
+
+/// Iterator over the entries of an `ArenaIntervalTree` that intersect a range.
+pub struct ArenaRangePairIter<'a, D: 'a> {
+    inner: NodeRangeIter<'a, D, (), Indexed>,
+}
+
+impl<'a, D: 'a> Iterator for ArenaRangePairIter<'a, D> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        self.inner.next()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::collections::BTreeMap;
+    use super::ArenaIntervalTree;
+    use node::check_invariants;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%50;
+        let len = rand::random::<u64>()%50;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_fuzz(){
+        let mut t = ArenaIntervalTree::<u64>::new();
+        let mut model = BTreeMap::new();
+        for i in 1..5000 {
+            let range = random_range();
+            if rand::random::<bool>() {
+                t.insert(range, i);
+                model.insert(range, i);
+                assert_eq!(t.get(range), Some(&i));
+            } else {
+                t.delete(range);
+                model.remove(&range);
+                assert!(!t.contains(range));
+            };
+            assert_eq!(check_invariants(&t.slots[..], t.root()), Ok(()));
+            assert_eq!(t.len(), model.len());
+            // deleted slots are reused, so the arena never grows beyond the peak size
+            assert!(t.slots.len() <= 50*50);
+        };
+        let query = random_range();
+        let should = model.iter().filter(|&(k,_)| k.intersect(&query)).map(|(k,v)| (*k,v)).collect::<Vec<_>>();
+        assert_eq!(t.range(query.min, query.max).collect::<Vec<_>>(), should);
+        assert_eq!(t.min().map(|(k,_)| *k), model.keys().next().cloned());
+        assert_eq!(t.max().map(|(k,_)| *k), model.keys().last().cloned());
+    }
+}
@@ -0,0 +1,222 @@
+use core::iter;
+use core::ops::RangeBounds;
+use alloc::vec;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::node::Node;
+use ::tree::IntervalTree;
+use ::observer::TreeObserver;
+use ::summary::Summary;
+use ::iterators::RangePairIter;
+use ::bounds::closed_range;
+
+/// Iterator over the results of a batch of queries, see `IntervalTree::range_many`.
+///
+/// For queries sorted by their lower bound, the tree is swept once from left to right: `active`
+/// holds (in key order) every key that starts before the frontier of the sweep and may still
+/// intersect later queries, and `cursor` walks the keys starting after `seed` in key order. A query
+/// only has to pull the keys starting before its upper bound from the cursor and drop the keys
+/// that ended before its lower bound. Whenever a query is smaller than its predecessor, the sweep is
+/// restarted at the new query by a regular range query, so unsorted input is answered correctly,
+/// just without the reuse.
+pub struct BatchQueryIter<'a, D: 'a, S: 'a, I> {
+    root: Option<&'a Node<D, S>>,
+    queries: iter::Enumerate<I>,
+    last: Option<Range>,
+    seed: u64,
+    active: Vec<(Range, &'a D)>,
+    cursor: RangePairIter<'a, D, S>,
+    pending: Option<(Range, &'a D)>,
+    index: usize,
+    output: vec::IntoIter<(Range, &'a D)>,
+}
+
+/// The queries of `IntervalTree::stab_many`.
+pub type PointQueries<I> = iter::Map<I, fn(u64) -> Range>;
+
+/// The queries of `IntervalTree::range_many`.
+pub type RangeQueries<I> = iter::Map<I, fn((u64, u64)) -> Range>;
+
+/// The queries of `IntervalTree::range_bounds_many`, empty bounds become `None`.
+pub type BoundsQueries<I, R> = iter::Map<I, fn(R) -> Option<Range>>;
+
+fn point_query(point: u64) -> Range {
+    Range::new(point, point)
+}
+
+fn range_query((min, max): (u64, u64)) -> Range {
+    Range::new(min, max)
+}
+
+fn bounds_query<R: RangeBounds<u64>>(bounds: R) -> Option<Range> {
+    closed_range(&bounds)
+}
+
+impl<'a, D: 'a, S: Summary<D> + 'a, I: Iterator> BatchQueryIter<'a, D, S, I> where I::Item: Into<Option<Range>> {
+
+    fn new(root: Option<&'a Node<D, S>>, queries: I) -> BatchQueryIter<'a, D, S, I> {
+        BatchQueryIter { root, queries: queries.enumerate(), last: None, seed: 0, active: Vec::new(),
+            cursor: RangePairIter::empty(), pending: None, index: 0, output: Vec::new().into_iter() }
+    }
+
+    /// restarts the sweep at point
+    fn seed(&mut self, point: u64) {
+        self.seed = point;
+        self.active = RangePairIter::from_root(self.root, point, point).collect();
+        self.cursor = if point == 0xffff_ffff_ffff_ffff {
+            RangePairIter::empty()
+        } else {
+            RangePairIter::from_root(self.root, point+1, 0xffff_ffff_ffff_ffff)
+        };
+        self.pending = None;
+    }
+
+    /// the next key in key order that starts after the seed
+    fn peek_cursor(&mut self) -> Option<(Range, &'a D)> {
+        if self.pending.is_none() {
+            let seed = self.seed;
+            self.pending = self.cursor.by_ref().find(|&(key, _)| key.min > seed);
+        }
+        self.pending
+    }
+
+    fn answer(&mut self, query: Option<Range>) -> Vec<(Range, &'a D)> {
+        // empty bounds intersect nothing and leave the sweep where it is
+        let query = match query { Some(query) => query, None => return Vec::new() };
+        if self.last.is_none_or(|last| query.min < last.min) {
+            self.seed(query.min);
+        }
+        self.last = Some(query);
+        while let Some(entry) = self.peek_cursor() {
+            if entry.0.min > query.max { break }
+            self.active.push(entry);
+            self.pending = None;
+        }
+        self.active.retain(|&(key, _)| key.max >= query.min);
+        self.active.iter().filter(|&&(key, _)| key.min <= query.max).cloned().collect()
+    }
+}
+
+impl<'a, D: 'a, S: Summary<D> + 'a, I: Iterator> Iterator for BatchQueryIter<'a, D, S, I> where I::Item: Into<Option<Range>> {
+
+    type Item = (usize, Range, &'a D);
+
+    fn next(&mut self) -> Option<(usize, Range, &'a D)> {
+        loop {
+            if let Some((key, data)) = self.output.next() {
+                return Some((self.index, key, data))
+            }
+            let (index, query) = self.queries.next()?;
+            self.index = index;
+            self.output = self.answer(query.into()).into_iter();
+        }
+    }
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will return an iterator over the (query index, key, value) triples of all keys
+/// containing one of the given points. The results of each point are returned in key order, and the
+/// points in the order in which they were given. If the points are sorted, the tree is only swept
+/// once instead of being queried from the root for every single point; unsorted points are
+/// answered correctly as well, but every descending point restarts the sweep.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(0,10),1);
+/// t.insert(Range::new(5,20),2);
+/// assert_eq!(t.stab_many(vec![3, 7, 15]).map(|(i,_,v)| (i,*v)).collect::<Vec<_>>(),
+///     vec![(0,1), (1,1), (1,2), (2,2)]);
+/// ```
+    pub fn stab_many<P>(&self, points: P) -> BatchQueryIter<'_, D, S, PointQueries<P::IntoIter>>
+        where P: IntoIterator<Item=u64> {
+        BatchQueryIter::new(self.root.as_deref(), points.into_iter().map(point_query as fn(u64) -> Range))
+    }
+
+/// This function will return an iterator over the (query index, key, value) triples of all keys
+/// intersecting one of the given (min,max) queries, like `stab_many` does for points. The sweep is
+/// reused as long as the lower bounds of the queries don't decrease.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(0,10),1);
+/// t.insert(Range::new(5,20),2);
+/// assert_eq!(t.range_many(vec![(11,30), (0,4)]).map(|(i,_,v)| (i,*v)).collect::<Vec<_>>(),
+///     vec![(0,2), (1,1)]);
+/// ```
+    pub fn range_many<Q>(&self, queries: Q) -> BatchQueryIter<'_, D, S, RangeQueries<Q::IntoIter>>
+        where Q: IntoIterator<Item=(u64, u64)> {
+        BatchQueryIter::new(self.root.as_deref(), queries.into_iter().map(range_query as fn((u64, u64)) -> Range))
+    }
+
+/// This function will return an iterator over the (query index, key, value) triples of all keys
+/// intersecting one of the given bounds, like `range_many` does for (min,max) queries. The bounds
+/// may be inclusive, exclusive or unbounded on either side, and empty bounds yield nothing.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(0,10),1);
+/// t.insert(Range::new(5,20),2);
+/// assert_eq!(t.range_bounds_many(vec![11..30, 4..4, 0..5]).map(|(i,_,v)| (i,*v)).collect::<Vec<_>>(),
+///     vec![(0,2), (2,1)]);
+/// ```
+    pub fn range_bounds_many<Q, R>(&self, queries: Q) -> BatchQueryIter<'_, D, S, BoundsQueries<Q::IntoIter, R>>
+        where Q: IntoIterator<Item=R>, R: RangeBounds<u64> {
+        BatchQueryIter::new(self.root.as_deref(), queries.into_iter().map(bounds_query::<R> as fn(R) -> Option<Range>))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+
+    fn random_range(max_len: u64) -> memrange::Range {
+        let offset = rand::random::<u64>()%1000;
+        let len = rand::random::<u64>()%max_len;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    fn one_by_one(t: &::IntervalTree<u64>, queries: &[(u64, u64)]) -> Vec<(usize, memrange::Range, u64)> {
+        queries.iter().enumerate()
+            .flat_map(|(i, &(min, max))| t.range(min, max).map(move |(k, v)| (i, k, *v)))
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_queries(){
+        let mut t = ::IntervalTree::<u64>::new();
+        for i in 0..300 {
+            t.insert(random_range(100), i);
+        }
+        for &sorted in &[true, false] {
+            let mut points = (0..200).map(|_| rand::random::<u64>()%1100).collect::<Vec<_>>();
+            if sorted { points.sort() }
+            let queries = points.iter().map(|&p| (p, p)).collect::<Vec<_>>();
+            assert_eq!(t.stab_many(points.clone()).map(|(i,k,v)| (i,k,*v)).collect::<Vec<_>>(), one_by_one(&t, &queries));
+
+            let mut queries = (0..200).map(|_| random_range(60)).map(|r| (r.min, r.max)).collect::<Vec<_>>();
+            if sorted { queries.sort() }
+            assert_eq!(t.range_many(queries.clone()).map(|(i,k,v)| (i,k,*v)).collect::<Vec<_>>(), one_by_one(&t, &queries));
+
+            // half open bounds, where every fifth query is empty
+            let bounds = queries.iter().enumerate().map(|(i, &(min, max))| if i % 5 == 0 { min..min } else { min..max+1 }).collect::<Vec<_>>();
+            let should = bounds.iter().enumerate()
+                .flat_map(|(i, b)| t.range_bounds(b.clone()).map(move |(k, v)| (i, k, *v)))
+                .collect::<Vec<_>>();
+            assert_eq!(t.range_bounds_many(bounds).map(|(i,k,v)| (i,k,*v)).collect::<Vec<_>>(), should);
+        }
+        assert_eq!(t.stab_many(vec![!0, 0]).count(), t.range(0, 0).count());
+    }
+}
@@ -0,0 +1,342 @@
+use core::ops::{Bound, RangeBounds};
+use core::slice;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::tree::IntervalTree;
+use ::observer::TreeObserver;
+use ::summary::Summary;
+use ::iterators::RangePairIter;
+
+/// This function will convert any bounds over u64 (`a..b`, `a..=b`, `a..`, `..`, or a pair of
+/// `Bound`s with exclusive starts) into the closed `Range` that contains exactly the same points.
+/// Since keys are integers, every combination of inclusive, exclusive and unbounded ends has such a
+/// closed form, unless the bounds contain no point at all (e.g. `5..5`), in which case None is
+/// returned.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use std::ops::Bound;
+/// use theban_interval_tree::closed_range;
+///
+/// assert_eq!(closed_range(&(2..5)), Some(memrange::Range::new(2,4)));
+/// assert_eq!(closed_range(&(2..=5)), Some(memrange::Range::new(2,5)));
+/// assert_eq!(closed_range(&(Bound::Excluded(2), Bound::Unbounded)), Some(memrange::Range::new(3,!0)));
+/// assert_eq!(closed_range(&(5..5)), None);
+/// ```
+pub fn closed_range<R: RangeBounds<u64>>(bounds: &R) -> Option<Range> {
+    let min = match bounds.start_bound() {
+        Bound::Included(&min) => min,
+        Bound::Excluded(&min) => min.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let max = match bounds.end_bound() {
+        Bound::Included(&max) => max,
+        Bound::Excluded(&max) => max.checked_sub(1)?,
+        Bound::Unbounded => 0xffff_ffff_ffff_ffff,
+    };
+    if min <= max { Some(Range::new(min, max)) } else { None }
+}
+
+/// returns the values of the start and end bound as written, 0 and !0 for unbounded ends. Unlike
+/// `closed_range` exclusive ends are not adjusted, so `5..5` is empty but not inverted.
+pub(crate) fn bound_values<R: RangeBounds<u64>>(bounds: &R) -> (u64, u64) {
+    let min = match bounds.start_bound() {
+        Bound::Included(&min) | Bound::Excluded(&min) => min,
+        Bound::Unbounded => 0,
+    };
+    let max = match bounds.end_bound() {
+        Bound::Included(&max) | Bound::Excluded(&max) => max,
+        Bound::Unbounded => 0xffff_ffff_ffff_ffff,
+    };
+    (min, max)
+}
+
+/// An interval key that remembers how its ends were given: inclusive, exclusive or unbounded. Two
+/// `Bounds` are only equal if they have the same kinds of ends, so `10..20` and `10..=19` are
+/// different keys even though they contain the same points. Bounds that contain no point, like
+/// `30..30`, are valid keys too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bounds {
+    pub start: Bound<u64>,
+    pub end: Bound<u64>,
+}
+
+impl Bounds {
+
+/// This function will copy the ends of any bounds over u64.
+/// # Examples
+/// ```
+/// extern crate theban_interval_tree;
+/// use std::ops::Bound;
+/// use theban_interval_tree::Bounds;
+///
+/// assert_eq!(Bounds::new(&(10..20)), Bounds{ start: Bound::Included(10), end: Bound::Excluded(20) });
+/// assert!(Bounds::new(&(10..20)) != Bounds::new(&(10..=19)));
+/// ```
+    pub fn new<R: RangeBounds<u64>>(bounds: &R) -> Bounds {
+        Bounds { start: bounds.start_bound().cloned(), end: bounds.end_bound().cloned() }
+    }
+
+/// This function will return the closed `Range` containing the same points, or None if the bounds
+/// contain no point. See `closed_range`.
+    pub fn closed(&self) -> Option<Range> {
+        closed_range(self)
+    }
+
+/// This function will return true if the bounds contain no point.
+    pub fn is_empty(&self) -> bool {
+        self.closed().is_none()
+    }
+}
+
+impl RangeBounds<u64> for Bounds {
+    fn start_bound(&self) -> Bound<&u64> { self.start.as_ref() }
+
+    fn end_bound(&self) -> Bound<&u64> { self.end.as_ref() }
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will insert the data under the key described by `bounds`, overwriting the old data
+/// if the key is allready part of the tree. The key is stored as its `closed_range`, so `2..5` and
+/// `2..=4` are the same key and the subtree pruning of the queries stays exact. Returns the stored
+/// key, or gives the data back if the bounds are empty (like `5..5`): such intervals contain no
+/// point and have no closed form. Use a `BoundsIntervalTree` to keep the kinds of the bounds and
+/// to store empty intervals.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// assert_eq!(t.insert_bounds(10..20, 1), Ok(memrange::Range::new(10,19)));
+/// assert_eq!(t.insert_bounds(20.., 2), Ok(memrange::Range::new(20,!0)));
+/// assert_eq!(t.insert_bounds(30..30, 3), Err(3));
+/// assert_eq!(t.get(memrange::Range::new(10,19)), Some(&1));
+/// ```
+    pub fn insert_bounds<R: RangeBounds<u64>>(&mut self, bounds: R, data: D) -> Result<Range, D> {
+        match closed_range(&bounds) {
+            Some(key) => {
+                self.insert(key, data);
+                Ok(key)
+            },
+            None => Err(data)
+        }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs intersecting the
+/// given bounds, which may be inclusive, exclusive or unbounded on either side. Empty bounds yield
+/// nothing.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert_bounds(10..20, 1).unwrap();
+/// t.insert_bounds(20..30, 2).unwrap();
+/// assert_eq!(t.range_bounds(15..20).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1]);
+/// assert_eq!(t.range_bounds(15..=20).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1,2]);
+/// assert_eq!(t.range_bounds(..).count(), 2);
+/// assert_eq!(t.range_bounds(15..15).count(), 0);
+/// ```
+    pub fn range_bounds<R: RangeBounds<u64>>(&self, bounds: R) -> RangePairIter<'_, D, S> {
+        match closed_range(&bounds) {
+            Some(range) => RangePairIter::new(self, range.min, range.max),
+            None => RangePairIter::empty(),
+        }
+    }
+}
+
+/// An interval map whose keys keep the kinds of their bounds. Keys that contain points are stored
+/// in an `IntervalTree` under their `closed_range`, together with all other keys containing the
+/// same points (`10..20` and `10..=19`), so queries prune subtrees exactly like on closed keys. Keys
+/// that contain no point (like `30..30`) can be inserted, looked up and removed, but never
+/// intersect a query.
+/// # Examples
+/// ```
+/// extern crate theban_interval_tree;
+/// use theban_interval_tree::{Bounds, BoundsIntervalTree};
+///
+/// let mut t = BoundsIntervalTree::<i32>::new();
+/// t.insert(10..20, 1);
+/// t.insert(10..=19, 2);
+/// t.insert(30..30, 3);
+/// assert_eq!(t.get(&(10..20)), Some(&1));
+/// assert_eq!(t.get(&(30..30)), Some(&3));
+/// assert_eq!(t.range(15..).map(|(k,v)| (k,*v)).collect::<Vec<_>>(),
+///     vec![(Bounds::new(&(10..20)),1), (Bounds::new(&(10..=19)),2)]);
+/// assert_eq!(t.remove(&(30..30)), Some(3));
+/// ```
+#[derive(Debug)]
+pub struct BoundsIntervalTree<D> {
+    tree: IntervalTree<Vec<(Bounds, D)>>,
+    empty: Vec<(Bounds, D)>,
+    len: usize,
+}
+
+impl<D> Default for BoundsIntervalTree<D> {
+    fn default() -> BoundsIntervalTree<D> {
+        BoundsIntervalTree::new()
+    }
+}
+
+impl<D> BoundsIntervalTree<D> {
+
+/// This function will construct a new empty BoundsIntervalTree.
+    pub fn new() -> BoundsIntervalTree<D> {
+        BoundsIntervalTree { tree: IntervalTree::new(), empty: Vec::new(), len: 0 }
+    }
+
+    /// the entries sharing the points of bounds, or the empty keys
+    fn entries<R: RangeBounds<u64>>(&self, bounds: &R) -> Option<&Vec<(Bounds, D)>> {
+        match closed_range(bounds) {
+            Some(key) => self.tree.get(key),
+            None => Some(&self.empty),
+        }
+    }
+
+    /// calls f on the entries sharing the points of bounds and returns its result. The entries are
+    /// taken out of the tree and put back afterwards unless they are empty, so the tree never holds
+    /// empty lists.
+    fn update_entries<T, F>(&mut self, bounds: &Bounds, f: F) -> T where F: FnOnce(&mut Vec<(Bounds, D)>) -> T {
+        match bounds.closed() {
+            Some(key) => {
+                let mut entries = self.tree.remove(key).unwrap_or_default();
+                let result = f(&mut entries);
+                if !entries.is_empty() { self.tree.insert(key, entries) }
+                result
+            },
+            None => f(&mut self.empty),
+        }
+    }
+
+/// This function will insert the data under the key, overwriting and returning the old data if
+/// the key with the same kinds of bounds is allready part of the tree.
+    pub fn insert<R: RangeBounds<u64>>(&mut self, bounds: R, data: D) -> Option<D> {
+        let bounds = Bounds::new(&bounds);
+        let old = self.update_entries(&bounds, |entries| {
+            match entries.iter_mut().find(|entry| entry.0 == bounds) {
+                Some(entry) => Some(core::mem::replace(&mut entry.1, data)),
+                None => { entries.push((bounds, data)); None }
+            }
+        });
+        if old.is_none() { self.len += 1 }
+        old
+    }
+
+/// This function will remove the key and return its data, or None if the key with the same kinds
+/// of bounds is not part of the tree.
+    pub fn remove<R: RangeBounds<u64>>(&mut self, bounds: &R) -> Option<D> {
+        let bounds = Bounds::new(bounds);
+        if !self.contains(&bounds) { return None }
+        let data = self.update_entries(&bounds, |entries| {
+            let i = entries.iter().position(|entry| entry.0 == bounds).expect("key is part of the tree");
+            entries.remove(i).1
+        });
+        self.len -= 1;
+        Some(data)
+    }
+
+/// This function will return the Some(data) stored under the key with the same kinds of bounds,
+/// or None if the key is not known.
+    pub fn get<R: RangeBounds<u64>>(&self, bounds: &R) -> Option<&D> {
+        let bounds = Bounds::new(bounds);
+        self.entries(&bounds)?.iter().find(|entry| entry.0 == bounds).map(|entry| &entry.1)
+    }
+
+/// This function will return true if the tree contains the key with the same kinds of bounds.
+    pub fn contains<R: RangeBounds<u64>>(&self, bounds: &R) -> bool {
+        self.get(bounds).is_some()
+    }
+
+/// This function will return the number of key,value pairs in the tree.
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return true if the tree is empty, false otherwise.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+/// This function will return a read only iterator for all (key,value) pairs in the tree: the keys
+/// containing points ordered by their `closed_range` (keys with the same closed form in insertion
+/// order), followed by the keys containing no point in insertion order.
+    pub fn iter(&self) -> BoundsRangeIter<'_, D> {
+        BoundsRangeIter { ranges: self.tree.iter(), entries: [].iter(), empty: self.empty.iter() }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs that share a point
+/// with the given bounds, ordered like `iter`. Keys containing no point are never returned.
+    pub fn range<R: RangeBounds<u64>>(&self, bounds: R) -> BoundsRangeIter<'_, D> {
+        BoundsRangeIter { ranges: self.tree.range_bounds(bounds), entries: [].iter(), empty: [].iter() }
+    }
+}
+
+/// Iterator over the entries of a `BoundsIntervalTree`.
+pub struct BoundsRangeIter<'a, D: 'a> {
+    ranges: RangePairIter<'a, Vec<(Bounds, D)>>,
+    entries: slice::Iter<'a, (Bounds, D)>,
+    empty: slice::Iter<'a, (Bounds, D)>,
+}
+
+impl<'a, D: 'a> Iterator for BoundsRangeIter<'a, D> {
+
+    type Item = (Bounds, &'a D);
+
+    fn next(&mut self) -> Option<(Bounds, &'a D)> {
+        loop {
+            if let Some(&(bounds, ref data)) = self.entries.next() { return Some((bounds, data)) }
+            match self.ranges.next() {
+                Some((_, entries)) => self.entries = entries.iter(),
+                None => return self.empty.next().map(|&(bounds, ref data)| (bounds, data)),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use rand;
+    use std::ops::{Bound, RangeBounds};
+    use super::{Bounds, BoundsIntervalTree};
+
+    fn random_bound() -> Bound<u64> {
+        let value = rand::random::<u64>() % 30;
+        match rand::random::<u8>() % 3 {
+            0 => Bound::Included(value),
+            1 => Bound::Excluded(value),
+            _ => Bound::Unbounded,
+        }
+    }
+
+    fn random_bounds() -> Bounds {
+        Bounds { start: random_bound(), end: random_bound() }
+    }
+
+    #[test]
+    fn test_bounds_fuzz(){
+        let mut t = BoundsIntervalTree::new();
+        let mut model: Vec<(Bounds, u32)> = Vec::new();
+        for i in 0..3000 {
+            let key = random_bounds();
+            if rand::random::<u8>() < 160 {
+                let old = model.iter().position(|e| e.0 == key).map(|j| std::mem::replace(&mut model[j].1, i));
+                if old.is_none() { model.push((key, i)) }
+                assert_eq!(t.insert(key, i), old);
+            } else {
+                let old = model.iter().position(|e| e.0 == key).map(|j| model.remove(j).1);
+                assert_eq!(t.remove(&key), old);
+            }
+            assert_eq!(t.len(), model.len());
+            assert_eq!(t.get(&key), model.iter().find(|e| e.0 == key).map(|e| &e.1));
+            let query = random_bounds();
+            let mut found = t.range(query).map(|(k, v)| (k, *v)).collect::<Vec<_>>();
+            let mut should = model.iter()
+                .filter(|e| (0..31).chain(Some(!0)).any(|p| e.0.contains(&p) && query.contains(&p)))
+                .cloned().collect::<Vec<_>>();
+            found.sort_by_key(|e| e.1);
+            should.sort_by_key(|e| e.1);
+            assert_eq!(found, should, "query {:?}", query);
+        }
+        assert_eq!(t.iter().count(), model.len());
+    }
+}
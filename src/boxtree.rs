@@ -0,0 +1,288 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::node::Node;
+use ::tree::IntervalTree;
+use ::summary::Summary;
+
+/// All boxes that share the same range in the first dimension, together with their data.
+type Bucket<D, const N: usize> = Vec<([Range; N], D)>;
+
+/// The smallest box containing every box of a subtree, None for empty subtrees. It is maintained
+/// as the summary of the underlying `IntervalTree`, so subtrees whose bounding box misses a query
+/// in any dimension are pruned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox<const N: usize>(pub Option<[Range; N]>);
+
+impl<const N: usize> BoundingBox<N> {
+    fn union(&self, other: &[Range; N]) -> BoundingBox<N> {
+        match self.0 {
+            Some(ref own) => {
+                let mut result = *own;
+                for (dim, range) in result.iter_mut().enumerate() { *range = range.get_union(&other[dim]) }
+                BoundingBox(Some(result))
+            },
+            None => BoundingBox(Some(*other))
+        }
+    }
+
+    fn intersects(&self, query: &[Range; N]) -> bool {
+        self.0.is_some_and(|own| own.iter().zip(query.iter()).all(|(a, b)| a.intersect(b)))
+    }
+}
+
+impl<D, const N: usize> Summary<Bucket<D, N>> for BoundingBox<N> {
+    fn identity() -> BoundingBox<N> { BoundingBox(None) }
+
+    fn from_entry(_key: &Range, bucket: &Bucket<D, N>) -> BoundingBox<N> {
+        bucket.iter().fold(BoundingBox(None), |acc, (bounds, _)| acc.union(bounds))
+    }
+
+    fn combine(&self, other: &BoundingBox<N>) -> BoundingBox<N> {
+        match other.0 {
+            Some(ref other) => self.union(other),
+            None => *self
+        }
+    }
+}
+
+fn intersects<const N: usize>(a: &[Range; N], b: &[Range; N]) -> bool {
+    a.iter().zip(b.iter()).all(|(a, b)| a.intersect(b))
+}
+
+fn contains<const N: usize>(outer: &[Range; N], inner: &[Range; N]) -> bool {
+    outer.iter().zip(inner.iter()).all(|(o, i)| o.min <= i.min && i.max <= o.max)
+}
+
+/// collects every box below node that intersects query and is accepted by filter, in key order of
+/// the first dimension. Subtrees are pruned by the max of the first dimension like in `range` and
+/// by their bounding box in all dimensions.
+fn collect_matching<'a, D, F, const N: usize>(node: &'a Option<Box<Node<Bucket<D, N>, BoundingBox<N>>>>,
+        query: &[Range; N], filter: &F, out: &mut Vec<(&'a [Range; N], &'a D)>)
+        where F: Fn(&[Range; N]) -> bool {
+    let node = match *node {
+        Some(ref node) if node.summary.intersects(query) => node,
+        _ => return
+    };
+    if node.left_subtree_relevant(&query[0]) { collect_matching(&node.left, query, filter, out) }
+    if node.key.intersect(&query[0]) {
+        for (bounds, data) in &node.data {
+            if intersects(bounds, query) && filter(bounds) { out.push((bounds, data)) }
+        }
+    }
+    if node.right_subtree_relevant(&query[0]) { collect_matching(&node.right, query, filter, out) }
+}
+
+/// An index of axis-aligned boxes in N dimensions, each given as N closed `Range`s. Boxes are
+/// stored in an `IntervalTree` keyed by their first dimension, whose subtrees additionally cache
+/// the bounding box of all their boxes. Queries are pruned by the subtree max of the first
+/// dimension and by the bounding boxes in all dimensions. N has to be at least 1.
+#[derive(Debug)]
+pub struct BoxTree<D, const N: usize> {
+    tree: IntervalTree<Bucket<D, N>, BoundingBox<N>>,
+    len: usize,
+}
+
+impl<D, const N: usize> BoxTree<D, N> {
+
+/// This function will construct a new empty BoxTree. A BoxTree without dimensions does not
+/// compile.
+/// # Examples
+/// ```
+/// extern crate theban_interval_tree;
+/// let t = theban_interval_tree::BoxTree::<i32, 2>::new();
+/// assert!(t.is_empty());
+/// ```
+/// ```compile_fail
+/// extern crate theban_interval_tree;
+/// let t = theban_interval_tree::BoxTree::<i32, 0>::new();
+/// ```
+    pub fn new() -> BoxTree<D, N> {
+        const { assert!(N > 0, "a BoxTree needs at least one dimension") };
+        BoxTree { tree: IntervalTree::with_summary(), len: 0 }
+    }
+
+/// This function will insert data under the given box. If the exact same box is already stored,
+/// its data is replaced.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t = theban_interval_tree::BoxTree::<i32, 2>::new();
+/// t.insert([Range::new(0,10), Range::new(0,10)], 1);
+/// t.insert([Range::new(0,10), Range::new(0,10)], 2);
+/// assert_eq!(t.len(), 1);
+/// assert_eq!(t.get(&[Range::new(0,10), Range::new(0,10)]), Some(&2));
+/// ```
+    pub fn insert(&mut self, bounds: [Range; N], data: D) {
+        let mut bucket = self.tree.remove(bounds[0]).unwrap_or_default();
+        match bucket.iter().position(|entry| entry.0 == bounds) {
+            Some(index) => bucket[index].1 = data,
+            None => { bucket.push((bounds, data)); self.len += 1 }
+        }
+        self.tree.insert(bounds[0], bucket);
+    }
+
+/// This function will remove the given box and return its data, or None if it is not stored.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t = theban_interval_tree::BoxTree::<i32, 2>::new();
+/// t.insert([Range::new(0,10), Range::new(5,6)], 1);
+/// assert_eq!(t.remove(&[Range::new(0,10), Range::new(5,7)]), None);
+/// assert_eq!(t.remove(&[Range::new(0,10), Range::new(5,6)]), Some(1));
+/// assert!(t.is_empty());
+/// ```
+    pub fn remove(&mut self, bounds: &[Range; N]) -> Option<D> {
+        let mut bucket = self.tree.remove(bounds[0])?;
+        let removed = bucket.iter().position(|entry| entry.0 == *bounds).map(|index| bucket.swap_remove(index).1);
+        if removed.is_some() { self.len -= 1 }
+        if !bucket.is_empty() { self.tree.insert(bounds[0], bucket) }
+        removed
+    }
+
+/// This function will return the data stored under the given box, or None.
+    pub fn get(&self, bounds: &[Range; N]) -> Option<&D> {
+        self.tree.get(bounds[0])?.iter().find(|entry| entry.0 == *bounds).map(|entry| &entry.1)
+    }
+
+/// This function will return the number of boxes in the tree.
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return true if the tree contains no boxes.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+/// This function will return all boxes together with their data, ordered by their first dimension.
+    pub fn iter(&self) -> impl Iterator<Item = (&[Range; N], &D)> {
+        self.tree.iter().flat_map(|(_, bucket)| bucket.iter().map(|entry| (&entry.0, &entry.1)))
+    }
+
+/// This function will return all boxes that intersect query in every dimension, ordered by their
+/// first dimension.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t = theban_interval_tree::BoxTree::<i32, 2>::new();
+/// t.insert([Range::new(0,10), Range::new(0,10)], 1);
+/// t.insert([Range::new(5,20), Range::new(30,40)], 2);
+/// let found = t.overlapping(&[Range::new(8,9), Range::new(8,35)]);
+/// assert_eq!(found.len(), 2);
+/// assert_eq!(t.overlapping(&[Range::new(8,9), Range::new(20,25)]).len(), 0);
+/// ```
+    pub fn overlapping(&self, query: &[Range; N]) -> Vec<(&[Range; N], &D)> {
+        let mut result = Vec::new();
+        collect_matching(&self.tree.root, query, &|_: &[Range; N]| true, &mut result);
+        result
+    }
+
+/// This function will return all boxes that lie completely within query, ordered by their first
+/// dimension.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t = theban_interval_tree::BoxTree::<i32, 2>::new();
+/// t.insert([Range::new(0,10), Range::new(0,10)], 1);
+/// t.insert([Range::new(2,3), Range::new(4,5)], 2);
+/// let found = t.contained_in(&[Range::new(1,10), Range::new(0,10)]);
+/// assert_eq!(found, vec![(&[Range::new(2,3), Range::new(4,5)], &2)]);
+/// ```
+    pub fn contained_in(&self, query: &[Range; N]) -> Vec<(&[Range; N], &D)> {
+        let mut result = Vec::new();
+        collect_matching(&self.tree.root, query, &|bounds: &[Range; N]| contains(query, bounds), &mut result);
+        result
+    }
+
+/// This function will return all boxes that contain the given point, ordered by their first
+/// dimension.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t = theban_interval_tree::BoxTree::<i32, 2>::new();
+/// t.insert([Range::new(0,10), Range::new(0,10)], 1);
+/// t.insert([Range::new(5,20), Range::new(30,40)], 2);
+/// assert_eq!(t.containing_point([7, 35]), vec![(&[Range::new(5,20), Range::new(30,40)], &2)]);
+/// ```
+    pub fn containing_point(&self, point: [u64; N]) -> Vec<(&[Range; N], &D)> {
+        let mut query = [Range::new(0, 0); N];
+        for (range, &p) in query.iter_mut().zip(point.iter()) { *range = Range::new(p, p) }
+        self.overlapping(&query)
+    }
+}
+
+impl<D, const N: usize> Default for BoxTree<D, N> {
+    fn default() -> BoxTree<D, N> { BoxTree::new() }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use super::{BoxTree, contains, intersects};
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%100;
+        let len = rand::random::<u64>()%20;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    fn random_box<const N: usize>() -> [memrange::Range; N] {
+        let mut result = [memrange::Range::new(0, 0); N];
+        for range in result.iter_mut() { *range = random_range() }
+        // few distinct first dimensions, so that buckets hold several boxes
+        result[0] = memrange::Range::new(result[0].min % 10, result[0].min % 10 + 5);
+        result
+    }
+
+    fn sorted<'a, D: Ord, const N: usize>(mut v: Vec<(&'a [memrange::Range; N], &'a D)>) -> Vec<(&'a [memrange::Range; N], &'a D)> {
+        v.sort();
+        v
+    }
+
+    fn fuzz<const N: usize>() {
+        let mut t = BoxTree::<u64, N>::new();
+        let mut model: Vec<([memrange::Range; N], u64)> = vec![];
+        for i in 0..2000 {
+            let b = random_box::<N>();
+            if rand::random::<u8>() < 85 {
+                let expected = model.iter().position(|e| e.0 == b).map(|index| model.swap_remove(index).1);
+                assert_eq!(t.remove(&b), expected);
+            } else {
+                model.retain(|e| e.0 != b);
+                model.push((b, i));
+                t.insert(b, i);
+            }
+            assert_eq!(t.len(), model.len());
+            assert!(t.tree.check_invariants().is_ok());
+        }
+        for _ in 0..200 {
+            let q = random_box::<N>();
+            let mut p = [0u64; N];
+            for (dim, p) in p.iter_mut().enumerate() { *p = q[dim].min }
+            let should = |f: &dyn Fn(&[memrange::Range; N]) -> bool| sorted(model.iter().filter(|e| f(&e.0)).map(|e| (&e.0, &e.1)).collect());
+            assert_eq!(sorted(t.overlapping(&q)), should(&|b| intersects(b, &q)));
+            assert_eq!(sorted(t.contained_in(&q)), should(&|b| contains(&q, b)));
+            assert_eq!(sorted(t.containing_point(p)), should(&|b| b.iter().zip(p.iter()).all(|(r, p)| r.min <= *p && *p <= r.max)));
+        }
+        assert_eq!(sorted(t.iter().collect()), sorted(model.iter().map(|e| (&e.0, &e.1)).collect()));
+    }
+
+    #[test]
+    fn test_box_tree_fuzz(){
+        fuzz::<1>();
+        fuzz::<2>();
+        fuzz::<3>();
+    }
+}
@@ -0,0 +1,32 @@
+use alloc::vec::Vec;
+use ::range::Range;
+use ::node::build_balanced;
+use ::tree::IntervalTree;
+use ::error::{Error, check_sorted};
+use ::summary::Summary;
+use ::observer::TreeObserver;
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will build a tree from entries that are sorted by key without duplicates, e.g.
+/// the output of `iter`, in O(n) instead of the O(n log(n)) of inserting them one by one. If a key
+/// is not bigger than the one before it, `Error::Unsorted` is returned.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::{IntervalTree, Error};
+///
+/// let t = IntervalTree::<i32>::from_sorted(vec![(Range::new(1,5),1), (Range::new(2,3),2)]).unwrap();
+/// assert_eq!(t.range(4,4).count(), 1);
+/// let err = IntervalTree::<i32>::from_sorted(vec![(Range::new(2,3),2), (Range::new(1,5),1)]);
+/// assert_eq!(err.err(), Some(Error::Unsorted(Range::new(1,5))));
+/// ```
+    pub fn from_sorted<I: IntoIterator<Item=(Range, D)>>(entries: I) -> Result<IntervalTree<D, S, O>, Error> {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        check_sorted(entries.iter().map(|(key, _)| key))?;
+        let len = entries.len();
+        Ok(IntervalTree::from_root(build_balanced(len, &mut entries.into_iter()), len))
+    }
+}
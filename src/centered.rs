@@ -0,0 +1,178 @@
+use core::cmp::Reverse;
+use core::ops;
+use core::ops::RangeBounds;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::bounds::closed_range;
+use ::node::into_sorted;
+use ::tree::IntervalTree;
+use ::index::IntervalIndex;
+use ::error::{Error, check_sorted};
+
+/// Index used instead of `None` for missing children.
+const NIL: u32 = 0xffff_ffff;
+
+/// A node of a centered interval tree. The intervals containing `center` are stored at
+/// `start..end` of both `by_min` and `by_max`.
+#[derive(Debug, Clone)]
+struct CenteredNode {
+    center: u64,
+    start: usize,
+    end: usize,
+    left: u32,
+    right: u32,
+}
+
+/// A static, classic centered interval tree. Every node picks a center point and stores the
+/// intervals containing it twice, sorted by `min` and by `max`. Intervals ending before the center
+/// go to the left subtree, those starting after it to the right one. Choosing the median endpoint
+/// as center keeps the depth at O(log n), a query then costs O(log n + k).
+#[derive(Debug, Clone)]
+pub struct CenteredIntervalTree<D> {
+    entries: Vec<(Range, D)>,
+    nodes: Vec<CenteredNode>,
+    /// indices into entries, for every node sorted by ascending min
+    by_min: Vec<u32>,
+    /// indices into entries, for every node sorted by descending max
+    by_max: Vec<u32>,
+    root: u32,
+}
+
+impl<D> CenteredIntervalTree<D> {
+
+    fn from_entries(entries: Vec<(Range, D)>) -> CenteredIntervalTree<D> {
+        assert!(entries.len() < NIL as usize, "CenteredIntervalTree can hold at most 2^32-1 entries");
+        let mut tree = CenteredIntervalTree { entries, nodes: Vec::new(), by_min: Vec::new(), by_max: Vec::new(), root: NIL };
+        let all = (0..tree.entries.len() as u32).collect::<Vec<_>>();
+        tree.root = tree.build(all);
+        tree
+    }
+
+    /// builds the subtree holding the entries at indices, which are sorted by key. Returns the
+    /// index of its root node.
+    fn build(&mut self, indices: Vec<u32>) -> u32 {
+        if indices.is_empty() { return NIL }
+        let mut endpoints = Vec::with_capacity(2 * indices.len());
+        for &i in &indices {
+            let key = self.entries[i as usize].0;
+            endpoints.push(key.min);
+            endpoints.push(key.max);
+        }
+        let mid = endpoints.len() / 2;
+        let center = *endpoints.select_nth_unstable(mid).1;
+        let (mut left, mut right, mut here) = (Vec::new(), Vec::new(), Vec::new());
+        for i in indices {
+            let key = self.entries[i as usize].0;
+            if key.max < center { left.push(i) } else if key.min > center { right.push(i) } else { here.push(i) }
+        }
+        let start = self.by_min.len();
+        self.by_min.extend(&here);
+        here.sort_by_key(|&i| Reverse(self.entries[i as usize].0.max));
+        self.by_max.extend(here);
+        let end = self.by_min.len();
+        let index = self.nodes.len() as u32;
+        self.nodes.push(CenteredNode { center, start, end, left: NIL, right: NIL });
+        let left = self.build(left);
+        let right = self.build(right);
+        let node = &mut self.nodes[index as usize];
+        node.left = left;
+        node.right = right;
+        index
+    }
+
+/// This function will build a centered interval tree from entries that are sorted by key without
+/// duplicates, e.g. the output of `IntervalTree::iter`. If a key is not bigger than the one before
+/// it, `Error::Unsorted` is returned.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::CenteredIntervalTree;
+///
+/// let t = CenteredIntervalTree::from_sorted(vec![(Range::new(1,5),1), (Range::new(7,9),2)]).unwrap();
+/// assert_eq!(t.stab(4).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1]);
+/// ```
+    pub fn from_sorted<I: IntoIterator<Item=(Range, D)>>(entries: I) -> Result<CenteredIntervalTree<D>, Error> {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        check_sorted(entries.iter().map(|(key, _)| key))?;
+        Ok(CenteredIntervalTree::from_entries(entries))
+    }
+
+/// This function will return the number of key,value pairs in the tree.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+/// This function will return true if the tree is empty, false otherwise.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+/// This function will return a read only iterator for all (key,value) pairs between the two
+/// bounds. The pairs are not returned in key order.
+    pub fn range(&self, min: u64, max: u64) -> CenteredRangePairIter<'_, D> {
+        let stack = if self.root == NIL { Vec::new() } else { vec![self.root] };
+        CenteredRangePairIter { tree: self, range: Range::new(min, max), stack, scan: 0..0, by_max: false }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs intersecting the given
+/// bounds, which may be inclusive, exclusive or unbounded on either side. Empty bounds yield
+/// nothing.
+    pub fn range_bounds<R: RangeBounds<u64>>(&self, bounds: R) -> CenteredRangePairIter<'_, D> {
+        match closed_range(&bounds) {
+            Some(range) => self.range(range.min, range.max),
+            None => CenteredRangePairIter { tree: self, range: Range::new(0, 0), stack: Vec::new(), scan: 0..0, by_max: false },
+        }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs containing the point.
+    pub fn stab(&self, point: u64) -> CenteredRangePairIter<'_, D> {
+        self.range(point, point)
+    }
+}
+
+impl<D, O: ?Sized> From<IntervalTree<D, (), O>> for CenteredIntervalTree<D> {
+    fn from(mut tree: IntervalTree<D, (), O>) -> CenteredIntervalTree<D> {
+        CenteredIntervalTree::from_entries(into_sorted(tree.root.take()))
+    }
+}
+
+impl<D> IntervalIndex<D> for CenteredIntervalTree<D> {
+    type RangeIter<'a> = CenteredRangePairIter<'a, D> where D: 'a;
+
+    fn range(&self, min: u64, max: u64) -> CenteredRangePairIter<'_, D> { CenteredIntervalTree::range(self, min, max) }
+
+    fn len(&self) -> usize { CenteredIntervalTree::len(self) }
+}
+
+/// Iterator over the entries of a `CenteredIntervalTree` that intersect a range. `scan` is the
+/// part of the list of the current node that is left to check, in `by_max` or in `by_min` order.
+pub struct CenteredRangePairIter<'a, D: 'a> {
+    tree: &'a CenteredIntervalTree<D>,
+    range: Range,
+    stack: Vec<u32>,
+    scan: ops::Range<usize>,
+    by_max: bool,
+}
+
+impl<'a, D: 'a> Iterator for CenteredRangePairIter<'a, D> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        loop {
+            if let Some(pos) = self.scan.next() {
+                let i = if self.by_max { self.tree.by_max[pos] } else { self.tree.by_min[pos] };
+                let (key, ref data) = self.tree.entries[i as usize];
+                // the list is sorted, so all remaining intervals miss the range as well
+                let done = if self.by_max { key.max < self.range.min } else { key.min > self.range.max };
+                if done { self.scan = 0..0; continue }
+                return Some((key, data))
+            }
+            let node = &self.tree.nodes[self.stack.pop()? as usize];
+            // all intervals of the node contain the center: if the range lies right of it only
+            // their max matters, otherwise only their min
+            self.by_max = self.range.min > node.center;
+            self.scan = node.start..node.end;
+            if node.left != NIL && self.range.min < node.center { self.stack.push(node.left) }
+            if node.right != NIL && self.range.max > node.center { self.stack.push(node.right) }
+        }
+    }
+}
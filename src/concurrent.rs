@@ -0,0 +1,294 @@
+use std::sync::{RwLock, RwLockReadGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::ops::RangeBounds;
+use ::range::Range;
+use ::tree::IntervalTree;
+use ::bounds::closed_range;
+
+/// An interval tree that can be shared between threads. The key space is split by `key.min` into
+/// shards, each of which is an `IntervalTree` behind its own `RwLock`. Writers only lock the shard
+/// owning their key, readers lock every shard that may hold an intersecting key. Since keys of a
+/// shard can reach into later shards, but never into earlier ones, a query for `[min, max]` has to
+/// visit the shards starting at or before `max`, except for those whose keys all end before `min`.
+///
+/// Queries that span several shards hold all their read locks at the same time, so they observe a
+/// consistent state of the tree: a result never contains a later write while missing an earlier one.
+#[derive(Debug)]
+pub struct ConcurrentIntervalTree<D> {
+    /// the smallest `key.min` of every shard, sorted and starting with 0
+    starts: Vec<u64>,
+    shards: Vec<Shard<D>>,
+}
+
+/// A part of a `ConcurrentIntervalTree`, with what queries need to know to skip it without locking.
+#[derive(Debug)]
+struct Shard<D> {
+    tree: RwLock<IntervalTree<D>>,
+    /// the subtree max of the tree, 0 if it is empty
+    max: AtomicU64,
+    /// incremented before and after every write, so it is odd while the tree is being changed
+    version: AtomicU64,
+}
+
+impl<D> ConcurrentIntervalTree<D> {
+
+/// This function will construct a new empty ConcurrentIntervalTree that splits the u64 key space
+/// into `shards` equally sized parts.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let t = Arc::new(theban_interval_tree::ConcurrentIntervalTree::<u64>::new(8));
+/// let handles = (0..4).map(|i| {
+///     let t = t.clone();
+///     thread::spawn(move || t.insert(memrange::Range::new(i*100, i*100+150), i))
+/// }).collect::<Vec<_>>();
+/// for handle in handles { handle.join().unwrap() }
+/// assert_eq!(t.stab(120).len(), 2);
+/// assert_eq!(t.len(), 4);
+/// ```
+    pub fn new(shards: usize) -> ConcurrentIntervalTree<D> {
+        assert!(shards > 0, "a ConcurrentIntervalTree needs at least one shard");
+        let width = (u64::MAX / shards as u64).saturating_add(1);
+        ConcurrentIntervalTree::with_boundaries((1..shards as u64).map(|i| i * width).collect())
+    }
+
+/// This function will construct a new empty ConcurrentIntervalTree whose shards start at 0 and at
+/// each of the given boundaries, e.g. to match a skewed distribution of keys. The boundaries have
+/// to be sorted and must not contain duplicates or 0.
+    pub fn with_boundaries(boundaries: Vec<u64>) -> ConcurrentIntervalTree<D> {
+        let mut starts = vec![0];
+        starts.extend(boundaries);
+        assert!(starts.windows(2).all(|w| w[0] < w[1]), "shard boundaries have to be strictly increasing and non zero");
+        let shards = starts.iter().map(|_| Shard { tree: RwLock::new(IntervalTree::new()), max: AtomicU64::new(0), version: AtomicU64::new(0) }).collect();
+        ConcurrentIntervalTree { starts, shards }
+    }
+
+/// This function will return the number of shards.
+    pub fn shard_count(&self) -> usize { self.shards.len() }
+
+    /// the index of the shard owning key
+    fn shard_of(&self, key: &Range) -> usize {
+        self.starts.partition_point(|start| *start <= key.min) - 1
+    }
+
+    /// write locks the shard owning key, applies f to its tree and publishes the new subtree max
+    fn write<F, R>(&self, key: &Range, f: F) -> R where F: FnOnce(&mut IntervalTree<D>) -> R {
+        let shard = &self.shards[self.shard_of(key)];
+        let mut tree = shard.tree.write().expect("shard lock poisoned");
+        shard.version.fetch_add(1, Ordering::SeqCst);
+        let result = f(&mut tree);
+        shard.max.store(tree.root().map_or(0, |root| root.subtree_max()), Ordering::SeqCst);
+        shard.version.fetch_add(1, Ordering::SeqCst);
+        result
+    }
+
+    fn read(&self, shard: usize) -> RwLockReadGuard<'_, IntervalTree<D>> {
+        self.shards[shard].tree.read().expect("shard lock poisoned")
+    }
+
+    /// read locks all shards that may hold keys intersecting [min, max], in ascending order. Shards
+    /// whose keys all end before min are skipped, if they did not change while the others were
+    /// locked: then there is a moment at which the locked shards hold what the query sees and the
+    /// skipped ones hold no intersecting key. Otherwise all shards up to max are locked.
+    fn read_range(&self, min: u64, max: u64) -> Vec<RwLockReadGuard<'_, IntervalTree<D>>> {
+        let shards = &self.shards[..self.starts.partition_point(|start| *start <= max)];
+        let versions = shards.iter().map(|shard| shard.version.load(Ordering::SeqCst)).collect::<Vec<_>>();
+        let skipped = shards.iter().zip(&versions)
+            .map(|(shard, version)| version % 2 == 0 && shard.max.load(Ordering::SeqCst) < min).collect::<Vec<_>>();
+        let guards = (0..shards.len()).filter(|&i| !skipped[i]).map(|i| self.read(i)).collect::<Vec<_>>();
+        if (0..shards.len()).all(|i| !skipped[i] || shards[i].version.load(Ordering::SeqCst) == versions[i]) {
+            return guards
+        }
+        drop(guards);
+        (0..shards.len()).map(|i| self.read(i)).collect()
+    }
+
+/// This function will insert the key,value pair into the tree, replacing the data already stored
+/// under this key. Only the shard owning the key is locked.
+    pub fn insert(&self, key: Range, data: D) {
+        self.write(&key, |tree| tree.insert(key, data))
+    }
+
+/// This function will remove the key from the tree and return the data stored under it, if any.
+    pub fn remove(&self, key: Range) -> Option<D> {
+        self.write(&key, |tree| tree.remove(key))
+    }
+
+/// This function will return true if the key is stored in the tree.
+    pub fn contains(&self, key: Range) -> bool {
+        self.read(self.shard_of(&key)).contains(key)
+    }
+
+/// This function will return true if the tree is empty.
+    pub fn empty(&self) -> bool {
+        self.read_range(0, u64::MAX).iter().all(|shard| shard.empty())
+    }
+
+/// This function will return the number of key,value pairs in the tree. All shards are read locked
+/// at the same time, so the count reflects a single point in time.
+    pub fn len(&self) -> usize {
+        self.read_range(0, u64::MAX).iter().map(|shard| shard.len()).sum()
+    }
+
+/// This function will return true if the tree is empty, false otherwise (same as `empty`).
+    pub fn is_empty(&self) -> bool { self.empty() }
+
+/// This function will call f for every (key,value) pair intersecting the two bounds in key order,
+/// while the read locks of all relevant shards are held.
+    pub fn for_each_in_range<F>(&self, min: u64, max: u64, mut f: F) where F: FnMut(Range, &D) {
+        for shard in self.read_range(min, max) {
+            for (key, data) in shard.range(min, max) { f(key, data) }
+        }
+    }
+
+/// This function will return the data stored under the key, if any.
+    pub fn get(&self, key: Range) -> Option<D> where D: Clone {
+        self.read(self.shard_of(&key)).get(key).cloned()
+    }
+
+/// This function will return all (key,value) pairs intersecting the two bounds in key order.
+    pub fn range(&self, min: u64, max: u64) -> Vec<(Range, D)> where D: Clone {
+        let mut result = Vec::new();
+        self.for_each_in_range(min, max, |key, data| result.push((key, data.clone())));
+        result
+    }
+
+/// This function will return all (key,value) pairs intersecting the given bounds in key order. The
+/// bounds may be inclusive, exclusive or unbounded on either side, empty bounds lock no shard and
+/// yield nothing.
+    pub fn range_bounds<R: RangeBounds<u64>>(&self, bounds: R) -> Vec<(Range, D)> where D: Clone {
+        match closed_range(&bounds) {
+            Some(range) => self.range(range.min, range.max),
+            None => Vec::new(),
+        }
+    }
+
+/// This function will return all (key,value) pairs containing the point in key order.
+    pub fn stab(&self, point: u64) -> Vec<(Range, D)> where D: Clone {
+        self.range(point, point)
+    }
+
+/// This function will return a copy of the whole tree. All shards are read locked at the same
+/// time, so the snapshot reflects a single point in time. The shards are visited in key order, so
+/// the copy is built bottom up instead of by inserting every entry.
+    pub fn snapshot(&self) -> IntervalTree<D> where D: Clone {
+        IntervalTree::from_sorted(self.range(0, u64::MAX)).expect("shards hold disjoint key ranges in order")
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::thread;
+    use super::ConcurrentIntervalTree;
+
+    const WRITERS: u64 = 4;
+    const STEPS: u64 = 2000;
+
+    /// keys of a writer are spread over all shards, and no two writers share a key
+    fn key_of(writer: u64, step: u64) -> memrange::Range {
+        let min = (step * 7919 % STEPS) * 1000 + writer;
+        memrange::Range::new(min, min + step % 3000)
+    }
+
+    #[test]
+    fn test_concurrent_stress(){
+        let t = Arc::new(ConcurrentIntervalTree::<(u64, u64)>::with_boundaries(vec![250_000, 500_000, 750_000, 1_000_000]));
+        let writers = (0..WRITERS).map(|writer| {
+            let t = t.clone();
+            thread::spawn(move || {
+                let mut model = BTreeMap::new();
+                for step in 0..STEPS {
+                    let key = key_of(writer, step);
+                    t.insert(key, (writer, step));
+                    model.insert(key, (writer, step));
+                    if step % 5 == 4 {
+                        let old = key_of(writer, step / 2);
+                        assert_eq!(t.remove(old), model.remove(&old));
+                    }
+                }
+                model
+            })
+        }).collect::<Vec<_>>();
+        let readers = (0..2).map(|_| {
+            let t = t.clone();
+            thread::spawn(move || {
+                for _ in 0..50 {
+                    let snapshot = t.snapshot();
+                    assert!(snapshot.check_invariants().is_ok());
+                    // a consistent snapshot shows every writer after some prefix of its operations.
+                    // Step s inserts key_of(s) and, if s % 5 == 4, then removes key_of(s / 2), so
+                    // given the newest visible step, every older step is known to be present or gone
+                    for writer in 0..WRITERS {
+                        let newest = match snapshot.iter().filter(|&(_, v)| v.0 == writer).map(|(_, v)| v.1).max() {
+                            Some(newest) => newest,
+                            None => continue
+                        };
+                        for step in 0..newest {
+                            let removers = (2 * step)..(2 * step + 2);
+                            let present = snapshot.contains(key_of(writer, step));
+                            if removers.clone().any(|s| s % 5 == 4 && s < newest) { assert!(!present) }
+                            if !removers.clone().any(|s| s % 5 == 4 && s <= newest) { assert!(present) }
+                        }
+                    }
+                    let q = rand::random::<u64>() % 2_000_000;
+                    let found = t.range(q, q + 5000);
+                    assert!(found.windows(2).all(|w| w[0].0 < w[1].0));
+                    assert!(found.iter().all(|&(k, _)| k.intersect(&memrange::Range::new(q, q + 5000))));
+                }
+            })
+        }).collect::<Vec<_>>();
+        let mut model = BTreeMap::new();
+        for writer in writers { model.extend(writer.join().expect("writer panicked")) }
+        for reader in readers { reader.join().expect("reader panicked") }
+
+        let snapshot = t.snapshot();
+        assert_eq!(snapshot.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(), model.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>());
+        for _ in 0..200 {
+            let q = rand::random::<u64>() % 2_000_000;
+            let len = rand::random::<u64>() % 10_000;
+            let should = model.iter().filter(|&(k, _)| k.intersect(&memrange::Range::new(q, q + len))).map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+            assert_eq!(t.range(q, q + len), should);
+        }
+    }
+
+    #[test]
+    fn test_even_shards(){
+        let t = ConcurrentIntervalTree::<u8>::new(3);
+        assert_eq!(t.shard_count(), 3);
+        t.insert(memrange::Range::new(0, 5), 0);
+        t.insert(memrange::Range::new(u64::MAX / 2, u64::MAX), 1);
+        t.insert(memrange::Range::new(u64::MAX, u64::MAX), 2);
+        assert_eq!(t.stab(u64::MAX).len(), 2);
+        assert_eq!(t.get(memrange::Range::new(0, 5)), Some(0));
+        assert_eq!(t.remove(memrange::Range::new(0, 5)), Some(0));
+        assert!(!t.contains(memrange::Range::new(0, 5)));
+        assert!(!t.empty());
+        assert_eq!(t.len(), 2);
+    }
+
+    #[test]
+    fn test_skipped_shards(){
+        let t = ConcurrentIntervalTree::<u8>::with_boundaries(vec![100, 200]);
+        t.insert(memrange::Range::new(10, 20), 0);
+        t.insert(memrange::Range::new(50, 250), 1);
+        t.insert(memrange::Range::new(150, 160), 2);
+        // the first shard reaches into the last one, the second one ends before the query
+        assert_eq!(t.read_range(210, 300).len(), 1);
+        assert_eq!(t.stab(210), vec![(memrange::Range::new(50, 250), 1)]);
+        t.remove(memrange::Range::new(50, 250));
+        assert_eq!(t.read_range(210, 300).len(), 0);
+        assert_eq!(t.read_range(155, 300).len(), 1);
+        assert_eq!(t.stab(155), vec![(memrange::Range::new(150, 160), 2)]);
+        assert_eq!(t.range_bounds(160..), vec![(memrange::Range::new(150, 160), 2)]);
+        assert_eq!(t.range_bounds(..150).len(), 1);
+        assert_eq!(t.range_bounds(..=150).len(), 2);
+        assert_eq!(t.range_bounds(155..155), vec![]);
+    }
+}
@@ -0,0 +1,169 @@
+use core::cmp::Reverse;
+use alloc::collections::BinaryHeap;
+use core::ops::RangeBounds;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::tree::IntervalTree;
+use ::observer::TreeObserver;
+use ::summary::Summary;
+use ::bounds::closed_range;
+
+// The depth of a point is the number of keys containing it. The maximal depth could be maintained
+// incrementally by a second tree keyed by endpoints, where every key adds +1 at its min and -1 after
+// its max and each node caches the maximal prefix sum of its subtree. The nodes of this tree are
+// keyed by whole intervals though, whose subtrees don't cover contiguous runs of endpoints, so they
+// can't carry that sum. Keeping a second tree would double the cost of every insert and delete for
+// all users, so the depth is computed on demand instead, by sweeping over the keys in the sorted
+// order of the tree.
+
+/// appends the segment to the profile, merging it with the last segment if both have the same depth
+fn push_segment(profile: &mut Vec<(Range, usize)>, segment: Range, depth: usize) {
+    match profile.last_mut() {
+        Some(last) if last.1 == depth => last.0.max = segment.max,
+        _ => profile.push((segment, depth))
+    }
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will return the number of keys that contain the given point.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(0,10),1);
+/// t.insert(memrange::Range::new(5,15),2);
+/// assert_eq!(t.depth_at(7), 2);
+/// assert_eq!(t.depth_at(12), 1);
+/// assert_eq!(t.depth_at(20), 0);
+/// ```
+    pub fn depth_at(&self, point: u64) -> usize {
+        self.range(point, point).count()
+    }
+
+/// This function will return a point that is contained in the maximal number of keys together with
+/// that number. If several points have the maximal depth, the smallest one is returned. An empty
+/// tree returns (0,0). Nothing is cached between calls, every call sweeps over the whole tree in
+/// O(n*log(n)).
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(0,10),1);
+/// t.insert(memrange::Range::new(5,15),2);
+/// t.insert(memrange::Range::new(8,9),3);
+/// t.insert(memrange::Range::new(20,30),4);
+/// assert_eq!(t.max_depth(), (8,3));
+/// ```
+    pub fn max_depth(&self) -> (u64, usize) {
+        let mut ends = BinaryHeap::new();
+        let mut best = (0, 0);
+        for (key, _) in self.iter() {
+            while ends.peek().is_some_and(|&Reverse(end)| end < key.min) {
+                ends.pop();
+            }
+            ends.push(Reverse(key.max));
+            if ends.len() > best.1 { best = (key.min, ends.len()) }
+        }
+        best
+    }
+
+/// This function will split the points within the given bounds into maximal segments of constant
+/// depth and return them in order, together with their depth. The segments cover all points of the
+/// bounds, including the parts with depth 0. Empty bounds return no segments.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(0,10),1);
+/// t.insert(Range::new(5,15),2);
+/// assert_eq!(t.depth_profile(3..=20),
+///     vec![(Range::new(3,4),1), (Range::new(5,10),2), (Range::new(11,15),1), (Range::new(16,20),0)]);
+/// ```
+    pub fn depth_profile<R: RangeBounds<u64>>(&self, bounds: R) -> Vec<(Range, usize)> {
+        let window = match closed_range(&bounds) {
+            Some(window) => window,
+            None => return Vec::new()
+        };
+        let (min, max) = (window.min, window.max);
+        let mut events = Vec::new();
+        for (key, _) in self.range(min, max) {
+            let clipped = key.get_intersection(&window);
+            events.push((clipped.min, 1isize));
+            if clipped.max < max { events.push((clipped.max + 1, -1)) }
+        }
+        events.sort();
+
+        let mut profile: Vec<(Range, usize)> = Vec::new();
+        let mut start = min;
+        let mut depth = 0isize;
+        for (pos, delta) in events {
+            if pos > start {
+                push_segment(&mut profile, Range::new(start, pos - 1), depth as usize);
+                start = pos;
+            }
+            depth += delta;
+        }
+        push_segment(&mut profile, Range::new(start, max), depth as usize);
+        profile
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%100;
+        let len = rand::random::<u64>()%20;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_depth_fuzz(){
+        for _ in 0..20 {
+            let mut t = ::IntervalTree::<i32>::new();
+            for _ in 0..rand::random::<usize>()%30 {
+                t.insert(random_range(), 0);
+            }
+            let depths = (0..130).map(|p| t.iter().filter(|&(k,_)| k.min <= p && p <= k.max).count()).collect::<Vec<_>>();
+            for (p, &depth) in depths.iter().enumerate() {
+                assert_eq!(t.depth_at(p as u64), depth);
+            }
+            let best = depths.iter().cloned().max().unwrap();
+            let best_point = depths.iter().position(|&d| d == best).unwrap() as u64;
+            assert_eq!(t.max_depth(), (best_point, best));
+
+            let q = random_range();
+            let profile = t.depth_profile(q.min..=q.max);
+            assert_eq!(profile.first().unwrap().0.min, q.min);
+            assert_eq!(profile.last().unwrap().0.max, q.max);
+            for (i, &(segment, depth)) in profile.iter().enumerate() {
+                if i > 0 {
+                    assert_eq!(profile[i-1].0.max + 1, segment.min);
+                    assert!(profile[i-1].1 != depth);
+                }
+                for p in segment.min..segment.max+1 {
+                    assert_eq!(depths[p as usize], depth);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_depth_unbounded(){
+        let mut t = ::IntervalTree::<i32>::new();
+        assert_eq!(t.max_depth(), (0,0));
+        t.insert(memrange::Range::new(5, !0), 1);
+        t.insert(memrange::Range::new(!0, !0), 2);
+        assert_eq!(t.max_depth(), (!0,2));
+        assert_eq!(t.depth_profile(..), vec![(memrange::Range::new(0,4),0), (memrange::Range::new(5,!0-1),1), (memrange::Range::new(!0,!0),2)]);
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(feature = "std")]
+use std::error;
+use core::fmt;
+use ::range::Range;
+use ::node::InvariantError;
+
+/// The errors reported by the fallible operations of the crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// The given interval has a lower bound that is bigger than its upper bound.
+    InvertedInterval { min: u64, max: u64 },
+    /// The key is allready part of the tree and overwriting it was not requested.
+    DuplicateKey(Range),
+    /// The tree violates one of its invariants, see `IntervalTree::check_invariants`.
+    InvariantViolation(InvariantError),
+    /// The entries given to a bulk constructor are not strictly increasing, the key is the first one
+    /// that is not bigger than its predecessor.
+    Unsorted(Range),
+    /// A tree with a fixed capacity is full and can't store a new key.
+    CapacityExceeded { capacity: usize },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvertedInterval { min, max } => write!(f, "interval {}..={} has min > max", min, max),
+            Error::DuplicateKey(key) => write!(f, "key {:?} is allready part of the tree", key),
+            Error::InvariantViolation(ref err) => write!(f, "broken interval tree: {}", err),
+            Error::Unsorted(key) => write!(f, "key {:?} is not bigger than the key before it", key),
+            Error::CapacityExceeded { capacity } => write!(f, "tree is full, it holds at most {} entries", capacity),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::InvariantViolation(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<InvariantError> for Error {
+    fn from(err: InvariantError) -> Error {
+        Error::InvariantViolation(err)
+    }
+}
+
+/// What `IntervalTree::try_insert` does if the key is allready part of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnDuplicate {
+    /// Replace the old data, like `IntervalTree::insert`.
+    Overwrite,
+    /// Keep the old data and return `Error::DuplicateKey`.
+    Reject,
+}
+
+/// returns an error if the interval is inverted
+pub fn check_interval(min: u64, max: u64) -> Result<(), Error> {
+    if min > max { Err(Error::InvertedInterval { min, max }) } else { Ok(()) }
+}
+
+/// returns an error for the first key that is not strictly bigger than its predecessor
+pub fn check_sorted<'a, I: Iterator<Item=&'a Range>>(keys: I) -> Result<(), Error> {
+    let mut last: Option<&Range> = None;
+    for key in keys {
+        if last.is_some_and(|last| last >= key) { return Err(Error::Unsorted(*key)) }
+        last = Some(key);
+    }
+    Ok(())
+}
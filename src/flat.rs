@@ -0,0 +1,258 @@
+use core::cmp;
+#[cfg(feature = "std")]
+use std::error;
+use core::convert::Infallible;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Write;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::tree::IntervalTree;
+use ::observer::TreeObserver;
+use ::summary::Summary;
+
+const HEADER_LEN: usize = 16;
+const ENTRY_LEN: usize = 32;
+
+/// The reasons why a byte slice could not be used as a flat tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlatError {
+    /// The buffer is shorter than the header.
+    TooShort,
+    /// The buffer does not start with `FlatTree::MAGIC`.
+    BadMagic,
+    /// The buffer length does not match the number of entries given in the header.
+    LengthMismatch { expected: u64, actual: u64 },
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FlatError::TooShort => write!(f, "buffer is too short to contain a flat tree header"),
+            FlatError::BadMagic => write!(f, "buffer does not start with the flat tree magic"),
+            FlatError::LengthMismatch { expected, actual } =>
+                write!(f, "flat tree should be {} bytes long, but buffer has {} bytes", expected, actual),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for FlatError {}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(buf)
+}
+
+/// Lays out the sorted entries in implicit (Eytzinger) order: the children of the entry at index
+/// `i` are stored at `2i+1` and `2i+2`. Returns (key, value, subtree max) triples.
+fn eytzinger_layout(sorted: &[(Range, u64)]) -> Vec<(Range, u64, u64)> {
+    let mut out = vec![(Range::new(0, 0), 0, 0); sorted.len()];
+    let mut next = 0;
+    fill_in_order(sorted, &mut out, 0, &mut next);
+    for i in (0..out.len()).rev() {
+        let mut max = out[i].0.max;
+        for child in &[2 * i + 1, 2 * i + 2] {
+            if *child < out.len() { max = cmp::max(max, out[*child].2) }
+        }
+        out[i].2 = max;
+    }
+    out
+}
+
+fn fill_in_order(sorted: &[(Range, u64)], out: &mut Vec<(Range, u64, u64)>, index: usize, next: &mut usize) {
+    if index >= out.len() { return }
+    fill_in_order(sorted, out, 2 * index + 1, next);
+    out[index] = (sorted[*next].0, sorted[*next].1, 0);
+    *next += 1;
+    fill_in_order(sorted, out, 2 * index + 2, next);
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+    /// passes the flat representation to write in pieces, stopping at the first error
+    fn encode_flat<F, W, E>(&self, value: F, mut write: W) -> Result<(), E>
+        where F: Fn(&D) -> u64, W: FnMut(&[u8]) -> Result<(), E> {
+        let sorted = self.iter().map(|(k, v)| (k, value(v))).collect::<Vec<_>>();
+        let layout = eytzinger_layout(&sorted);
+        write(FlatTree::MAGIC)?;
+        write(&(layout.len() as u64).to_le_bytes())?;
+        for &(key, value, max) in &layout {
+            write(&key.min.to_le_bytes())?;
+            write(&key.max.to_le_bytes())?;
+            write(&max.to_le_bytes())?;
+            write(&value.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+/// This function will write the tree in the flat format understood by `FlatTree`. Every value is
+/// mapped to an u64 by `value` (e.g. an index into a symbol table), the flat format does not store
+/// arbitrary data.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<u64>::new();
+/// t.insert(memrange::Range::new(2,8),25);
+/// let mut bytes = Vec::new();
+/// t.write_flat(&mut bytes, |v| *v).unwrap();
+/// let flat = theban_interval_tree::FlatTree::from_bytes(&bytes).unwrap();
+/// assert_eq!(flat.stab(5).collect::<Vec<_>>(), vec![(memrange::Range::new(2,8),25)]);
+/// ```
+    #[cfg(feature = "std")]
+    pub fn write_flat<W, F>(&self, out: &mut W, value: F) -> io::Result<()>
+        where W: Write, F: Fn(&D) -> u64 {
+        self.encode_flat(value, |bytes| out.write_all(bytes))
+    }
+
+/// This function will return the flat representation of the tree as produced by `write_flat`.
+    pub fn to_flat_bytes<F>(&self, value: F) -> Vec<u8> where F: Fn(&D) -> u64 {
+        let mut result = Vec::new();
+        let written: Result<(), Infallible> = self.encode_flat(value, |bytes| { result.extend_from_slice(bytes); Ok(()) });
+        match written { Ok(()) => result, Err(never) => match never {} }
+    }
+}
+
+/// A read only interval tree that is queried directly from its serialized bytes (e.g. a memory
+/// mapped file) without building any nodes. Entries are stored in implicit (Eytzinger) order
+/// together with the maximum of their subtree, each entry is four little endian u64: `min`, `max`,
+/// `subtree max` and `value`.
+#[derive(Debug, Clone, Copy)]
+pub struct FlatTree<'a> {
+    entries: &'a [u8],
+    len: usize,
+}
+
+impl<'a> FlatTree<'a> {
+
+/// The first eight bytes of every flat tree.
+    pub const MAGIC: &'static [u8; 8] = b"ITFLAT01";
+
+/// This function will check the header of the given bytes and return a tree view of them.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<FlatTree<'a>, FlatError> {
+        if bytes.len() < HEADER_LEN { return Err(FlatError::TooShort) }
+        if &bytes[0..8] != FlatTree::MAGIC { return Err(FlatError::BadMagic) }
+        let len = read_u64(bytes, 8);
+        let expected = len.checked_mul(ENTRY_LEN as u64).and_then(|l| l.checked_add(HEADER_LEN as u64));
+        if expected != Some(bytes.len() as u64) {
+            return Err(FlatError::LengthMismatch { expected: expected.unwrap_or(!0), actual: bytes.len() as u64 })
+        }
+        Ok(FlatTree { entries: &bytes[HEADER_LEN..], len: len as usize })
+    }
+
+/// This function will return the number of entries in the tree.
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return true if the tree has no entries.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+    fn key(&self, index: usize) -> Range {
+        Range { min: read_u64(self.entries, index * ENTRY_LEN), max: read_u64(self.entries, index * ENTRY_LEN + 8) }
+    }
+
+    fn subtree_max(&self, index: usize) -> u64 {
+        read_u64(self.entries, index * ENTRY_LEN + 16)
+    }
+
+    fn value(&self, index: usize) -> u64 {
+        read_u64(self.entries, index * ENTRY_LEN + 24)
+    }
+
+/// This function will return an iterator over all (key,value) pairs intersecting the two bounds, in
+/// the same order as `IntervalTree::range`.
+    pub fn range(&self, min: u64, max: u64) -> FlatRangeIter<'a> {
+        let mut stack = Vec::new();
+        if self.len > 0 { stack.push((0, false)) }
+        FlatRangeIter { tree: *self, range: Range::new(min, max), stack }
+    }
+
+/// This function will return an iterator over all (key,value) pairs containing the given point.
+    pub fn stab(&self, point: u64) -> FlatRangeIter<'a> {
+        self.range(point, point)
+    }
+
+/// This function will return an iterator over all (key,value) pairs in the tree.
+    pub fn iter(&self) -> FlatRangeIter<'a> {
+        self.range(0, 0xffff_ffff_ffff_ffff)
+    }
+}
+
+/// Iterator over the entries of a `FlatTree` that intersect a range. The stack holds entry indices
+/// and whether their left subtree was already visited.
+pub struct FlatRangeIter<'a> {
+    tree: FlatTree<'a>,
+    range: Range,
+    stack: Vec<(usize, bool)>,
+}
+
+impl<'a> Iterator for FlatRangeIter<'a> {
+
+    type Item = (Range, u64);
+
+    fn next(&mut self) -> Option<(Range, u64)> {
+        while let Some((index, left_done)) = self.stack.pop() {
+            let left = 2 * index + 1;
+            let right = 2 * index + 2;
+            if !left_done {
+                self.stack.push((index, true));
+                if left < self.tree.len && self.tree.subtree_max(left) >= self.range.min {
+                    self.stack.push((left, false));
+                }
+                continue
+            }
+            let key = self.tree.key(index);
+            if right < self.tree.len && self.range.max >= key.min {
+                self.stack.push((right, false));
+            }
+            if key.intersect(&self.range) { return Some((key, self.tree.value(index))) }
+        }
+        None
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use super::{FlatTree, FlatError};
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%1000;
+        let len = rand::random::<u64>()%50;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_flat_queries(){
+        let mut t = ::IntervalTree::<u64>::new();
+        for i in 0..500 {
+            t.insert(random_range(), i);
+        }
+        let bytes = t.to_flat_bytes(|v| *v);
+        let flat = FlatTree::from_bytes(&bytes).expect("valid flat tree");
+        assert_eq!(flat.iter().collect::<Vec<_>>(), t.iter().map(|(k,v)| (k,*v)).collect::<Vec<_>>());
+        for _ in 0..200 {
+            let query = random_range();
+            let should = t.range(query.min, query.max).map(|(k,v)| (k,*v)).collect::<Vec<_>>();
+            assert_eq!(flat.range(query.min, query.max).collect::<Vec<_>>(), should);
+            let should = t.range(query.min, query.min).map(|(k,v)| (k,*v)).collect::<Vec<_>>();
+            assert_eq!(flat.stab(query.min).collect::<Vec<_>>(), should);
+        }
+    }
+
+    #[test]
+    fn test_flat_errors(){
+        let t = ::IntervalTree::<u64>::new();
+        let mut bytes = t.to_flat_bytes(|v| *v);
+        assert!(FlatTree::from_bytes(&bytes).expect("empty tree is valid").is_empty());
+        assert_eq!(FlatTree::from_bytes(&bytes[0..4]).err(), Some(FlatError::TooShort));
+        bytes.push(0);
+        assert_eq!(FlatTree::from_bytes(&bytes).err(), Some(FlatError::LengthMismatch{expected: 16, actual: 17}));
+        bytes[0] = b'X';
+        assert_eq!(FlatTree::from_bytes(&bytes).err(), Some(FlatError::BadMagic));
+    }
+}
@@ -0,0 +1,263 @@
+use core::cmp;
+use core::ops;
+use core::ops::RangeBounds;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::bounds::closed_range;
+use ::node::{build_balanced, into_sorted};
+use ::tree::IntervalTree;
+use ::error::{Error, check_sorted};
+
+/// subranges of at most this many entries are scanned linearly instead of being split further
+const SCAN_LEN: usize = 8;
+
+/// A read only interval tree stored in contiguous arrays sorted by key. The tree is implicit: the
+/// entry in the middle of a subrange of the arrays is the root of that subrange, the entries left
+/// and right of it are its left and right subtree. `maxes` stores the biggest `key.max` of every
+/// subtree at the index of its root. Queries only touch `keys` and `maxes` and finish small
+/// subranges with a linear scan, so they need far fewer cache misses than walking boxed nodes.
+#[derive(Debug, Clone)]
+pub struct FrozenIntervalTree<D> {
+    keys: Vec<Range>,
+    maxes: Vec<u64>,
+    data: Vec<D>,
+}
+
+/// stores the biggest `key.max` of keys[lo..hi] at its middle, recursively for both halves.
+/// Returns that max, or 0 for an empty subrange.
+fn fill_maxes(keys: &[Range], maxes: &mut [u64], lo: usize, hi: usize) -> u64 {
+    if lo == hi { return 0 }
+    let mid = lo + (hi - lo) / 2;
+    let children = cmp::max(fill_maxes(keys, maxes, lo, mid), fill_maxes(keys, maxes, mid + 1, hi));
+    maxes[mid] = cmp::max(keys[mid].max, children);
+    maxes[mid]
+}
+
+impl<D> FrozenIntervalTree<D> {
+
+    fn from_entries(entries: Vec<(Range, D)>) -> FrozenIntervalTree<D> {
+        let (keys, data): (Vec<Range>, Vec<D>) = entries.into_iter().unzip();
+        let mut maxes = vec![0; keys.len()];
+        fill_maxes(&keys, &mut maxes, 0, keys.len());
+        FrozenIntervalTree { keys, maxes, data }
+    }
+
+/// This function will build a frozen tree from entries that are sorted by key without duplicates,
+/// e.g. the output of `IntervalTree::iter`. If a key is not bigger than the one before it,
+/// `Error::Unsorted` is returned.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::{FrozenIntervalTree, Error};
+///
+/// let t = FrozenIntervalTree::from_sorted(vec![(Range::new(1,5),1), (Range::new(2,3),2)]).unwrap();
+/// assert_eq!(t.count(4,4), 1);
+/// let err = FrozenIntervalTree::from_sorted(vec![(Range::new(2,3),2), (Range::new(1,5),1)]);
+/// assert_eq!(err.err(), Some(Error::Unsorted(Range::new(1,5))));
+/// ```
+    pub fn from_sorted<I: IntoIterator<Item=(Range, D)>>(entries: I) -> Result<FrozenIntervalTree<D>, Error> {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        check_sorted(entries.iter().map(|(key, _)| key))?;
+        Ok(FrozenIntervalTree::from_entries(entries))
+    }
+
+/// This function will turn the frozen tree back into a mutable IntervalTree in O(n).
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(3,8),1);
+/// let mut t = t.freeze().thaw();
+/// t.insert(memrange::Range::new(12,36),2);
+/// assert_eq!(t.iter().count(), 2);
+/// ```
+    pub fn thaw(self) -> IntervalTree<D> {
+        let len = self.keys.len();
+        IntervalTree::from_root(build_balanced(len, &mut self.keys.into_iter().zip(self.data)), len)
+    }
+
+/// This function will return the number of key,value pairs in the tree.
+    pub fn len(&self) -> usize { self.keys.len() }
+
+/// This function will return true if the tree is empty, false otherwise.
+    pub fn is_empty(&self) -> bool { self.keys.is_empty() }
+
+/// This function will return the Some(data) stored under the given key or None if the key is not
+/// known.
+    pub fn get(&self, key: Range) -> Option<&D> {
+        self.keys.binary_search(&key).ok().map(|i| &self.data[i])
+    }
+
+/// This function will return true if the tree contains the given key, false otherwise
+    pub fn contains(&self, key: Range) -> bool {
+        self.keys.binary_search(&key).is_ok()
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs in the tree.
+    pub fn iter(&self) -> FrozenRangePairIter<'_, D> {
+        self.range(0, 0xffff_ffff_ffff_ffff)
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs between the two
+/// bounds, in key order.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(3,8),1);
+/// t.insert(memrange::Range::new(12,36),2);
+/// let t = t.freeze();
+/// assert_eq!(t.range(9,14).map(|(k,_)| k.min).collect::<Vec<u64>>(), vec![12]);
+/// ```
+    pub fn range(&self, min: u64, max: u64) -> FrozenRangePairIter<'_, D> {
+        FrozenRangePairIter { tree: self, range: Range::new(min, max), scan: 0..0, stack: vec![(0, self.keys.len(), false)] }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs intersecting the given
+/// bounds, which may be inclusive, exclusive or unbounded on either side. Empty bounds yield
+/// nothing.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(3,8),1);
+/// t.insert(memrange::Range::new(12,36),2);
+/// let t = t.freeze();
+/// assert_eq!(t.range_bounds(8..12).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1]);
+/// assert_eq!(t.range_bounds(8..=12).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1,2]);
+/// assert_eq!(t.range_bounds(9..9).count(), 0);
+/// ```
+    pub fn range_bounds<R: RangeBounds<u64>>(&self, bounds: R) -> FrozenRangePairIter<'_, D> {
+        match closed_range(&bounds) {
+            Some(range) => self.range(range.min, range.max),
+            None => FrozenRangePairIter { tree: self, range: Range::new(0, 0), scan: 0..0, stack: Vec::new() },
+        }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs containing the point.
+    pub fn stab(&self, point: u64) -> FrozenRangePairIter<'_, D> {
+        self.range(point, point)
+    }
+
+/// This function will return the number of keys between the two bounds without reading their
+/// data.
+    pub fn count(&self, min: u64, max: u64) -> usize {
+        self.range(min, max).count()
+    }
+}
+
+impl<D, O: ?Sized> IntervalTree<D, (), O> {
+
+/// This function will turn the tree into a FrozenIntervalTree in O(n), which can't be modified
+/// anymore but answers queries faster. Use `FrozenIntervalTree::thaw` to modify it again.
+    pub fn freeze(mut self) -> FrozenIntervalTree<D> {
+        FrozenIntervalTree::from_entries(into_sorted(self.root.take()))
+    }
+}
+
+impl<D, O: ?Sized> From<IntervalTree<D, (), O>> for FrozenIntervalTree<D> {
+    fn from(tree: IntervalTree<D, (), O>) -> FrozenIntervalTree<D> {
+        tree.freeze()
+    }
+}
+
+/// Iterator over the entries of a `FrozenIntervalTree` that intersect a range. The stack holds
+/// subranges of the arrays and whether their left half was already visited, `scan` the rest of a
+/// small subrange that is scanned linearly.
+pub struct FrozenRangePairIter<'a, D: 'a> {
+    tree: &'a FrozenIntervalTree<D>,
+    range: Range,
+    scan: ops::Range<usize>,
+    stack: Vec<(usize, usize, bool)>,
+}
+
+impl<'a, D: 'a> FrozenRangePairIter<'a, D> {
+    /// ends the iteration, every remaining key starts after the range
+    fn finish(&mut self) -> Option<(Range, &'a D)> {
+        self.scan = 0..0;
+        self.stack.clear();
+        None
+    }
+}
+
+impl<'a, D: 'a> Iterator for FrozenRangePairIter<'a, D> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        loop {
+            if let Some(i) = self.scan.next() {
+                let key = self.tree.keys[i];
+                if key.min > self.range.max { return self.finish() }
+                if key.max >= self.range.min { return Some((key, &self.tree.data[i])) }
+                continue
+            }
+            let (lo, hi, left_done) = self.stack.pop()?;
+            let mid = lo + (hi - lo) / 2;
+            if !left_done {
+                if lo == hi || self.tree.maxes[mid] < self.range.min { continue }
+                if hi - lo <= SCAN_LEN { self.scan = lo..hi; continue }
+                self.stack.push((lo, hi, true));
+                self.stack.push((lo, mid, false));
+                continue
+            }
+            let key = self.tree.keys[mid];
+            if key.min > self.range.max { return self.finish() }
+            self.stack.push((mid + 1, hi, false));
+            if key.max >= self.range.min { return Some((key, &self.tree.data[mid])) }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use super::FrozenIntervalTree;
+    use error::Error;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%1000;
+        let len = rand::random::<u64>()%100;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_frozen_fuzz(){
+        for &len in &[0, 1, 7, 8, 9, 100, 2000] {
+            let mut t = ::IntervalTree::<usize>::new();
+            for i in 0..len { t.insert(random_range(), i) }
+            let entries = t.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>();
+            let frozen = t.freeze();
+            assert_eq!(frozen.len(), entries.len());
+            assert_eq!(frozen.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(), entries);
+            for _ in 0..200 {
+                let q = random_range();
+                let should = entries.iter().filter(|&(k, _)| k.intersect(&q)).cloned().collect::<Vec<_>>();
+                assert_eq!(frozen.range(q.min, q.max).map(|(k, v)| (k, *v)).collect::<Vec<_>>(), should);
+                assert_eq!(frozen.count(q.min, q.max), should.len());
+                assert_eq!(frozen.range_bounds(q.min..q.max+1).map(|(k, v)| (k, *v)).collect::<Vec<_>>(), should);
+                assert_eq!(frozen.range_bounds(q.min..q.min).count(), 0);
+                assert_eq!(frozen.stab(q.min).count(), entries.iter().filter(|&(k, _)| k.min <= q.min && q.min <= k.max).count());
+            }
+            for &(k, v) in entries.iter().take(20) { assert_eq!(frozen.get(k), Some(&v)) }
+            let thawed = frozen.thaw();
+            assert!(thawed.check_invariants().is_ok());
+            assert_eq!(thawed.len(), entries.len());
+            assert_eq!(thawed.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(), entries);
+        }
+    }
+
+    #[test]
+    fn test_frozen_unsorted(){
+        let entries = vec![(memrange::Range::new(1, 1), 0), (memrange::Range::new(1, 1), 1)];
+        assert_eq!(FrozenIntervalTree::from_sorted(entries).err(), Some(Error::Unsorted(memrange::Range::new(1, 1))));
+    }
+}
@@ -0,0 +1,279 @@
+use ::range::Range;
+use ::tree::IntervalTree;
+use ::iterators::RangePairIter;
+use ::arena::{ArenaIntervalTree, ArenaRangePairIter};
+use ::frozen::{FrozenIntervalTree, FrozenRangePairIter};
+use ::summary::Summary;
+use ::observer::TreeObserver;
+
+/// The queries shared by all interval indexes of this crate, so that code and tests can be written
+/// once and run against every backend, a mock or a `NaiveIntervalIndex`. Which backend is fastest
+/// depends on the data, the `backends` benchmark compares them on mostly disjoint and on heavily
+/// nested intervals.
+///
+/// The order of the results depends on the backend, the tree based ones return them in key order.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::{IntervalIndex, IntervalTree, NCList};
+///
+/// fn covering<I: IntervalIndex<u32>>(index: &I, point: u64) -> Vec<u32> {
+///     let mut found = index.stab(point).map(|(_, v)| *v).collect::<Vec<_>>();
+///     found.sort();
+///     found
+/// }
+///
+/// let entries = vec![(Range::new(1,10),1), (Range::new(2,3),2), (Range::new(5,8),3)];
+/// let nclist = NCList::from_sorted(entries.clone()).unwrap();
+/// let tree = IntervalTree::<u32>::from_sorted(entries).unwrap();
+/// assert_eq!(covering(&nclist, 6), vec![1,3]);
+/// assert_eq!(covering(&tree, 6), vec![1,3]);
+/// ```
+pub trait IntervalIndex<D> {
+    /// the iterator returned by `range` and `stab`
+    type RangeIter<'a>: Iterator<Item = (Range, &'a D)> where Self: 'a, D: 'a;
+
+    /// returns all (key,value) pairs intersecting the two bounds
+    fn range(&self, min: u64, max: u64) -> Self::RangeIter<'_>;
+
+    /// returns all (key,value) pairs containing the point
+    fn stab(&self, point: u64) -> Self::RangeIter<'_> {
+        self.range(point, point)
+    }
+
+    /// returns the data stored under the key. The default implementation searches the results of
+    /// `range`, backends that can look up keys directly override it.
+    fn get(&self, key: Range) -> Option<&D> {
+        self.range(key.min, key.max).find(|&(k, _)| k == key).map(|(_, data)| data)
+    }
+
+    /// returns the number of key,value pairs in the index
+    fn len(&self) -> usize;
+
+    /// returns true if the index is empty
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The updates shared by all mutable interval indexes of this crate.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::{IntervalIndexMut, IntervalTree, NaiveIntervalIndex};
+///
+/// fn fill<I: IntervalIndexMut<u64>>(index: &mut I) {
+///     for i in 0..10 { index.insert(Range::new(i, i+2), i) }
+///     index.remove(Range::new(3, 5));
+/// }
+///
+/// let mut tree = IntervalTree::<u64>::new();
+/// let mut naive = NaiveIntervalIndex::<u64>::new();
+/// fill(&mut tree);
+/// fill(&mut naive);
+/// assert!(tree.range(0, 20).eq(naive.range(0, 20)));
+/// ```
+pub trait IntervalIndexMut<D>: IntervalIndex<D> {
+    /// inserts the key,value pair, overwriting the old data if the key is allready part of the index
+    fn insert(&mut self, key: Range, data: D);
+
+    /// removes the key,value pair and returns the data, or None if the key is not part of the index
+    fn remove(&mut self, key: Range) -> Option<D>;
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalIndex<D> for IntervalTree<D, S, O> {
+    type RangeIter<'a> = RangePairIter<'a, D, S> where Self: 'a, D: 'a;
+
+    fn range(&self, min: u64, max: u64) -> RangePairIter<'_, D, S> { IntervalTree::range(self, min, max) }
+
+    fn get(&self, key: Range) -> Option<&D> { IntervalTree::get(self, key) }
+
+    fn len(&self) -> usize { IntervalTree::len(self) }
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalIndexMut<D> for IntervalTree<D, S, O> {
+    fn insert(&mut self, key: Range, data: D) { IntervalTree::insert(self, key, data) }
+
+    fn remove(&mut self, key: Range) -> Option<D> { IntervalTree::remove(self, key) }
+}
+
+impl<D> IntervalIndex<D> for ArenaIntervalTree<D> {
+    type RangeIter<'a> = ArenaRangePairIter<'a, D> where D: 'a;
+
+    fn range(&self, min: u64, max: u64) -> ArenaRangePairIter<'_, D> { ArenaIntervalTree::range(self, min, max) }
+
+    fn get(&self, key: Range) -> Option<&D> { ArenaIntervalTree::get(self, key) }
+
+    fn len(&self) -> usize { ArenaIntervalTree::len(self) }
+}
+
+impl<D> IntervalIndexMut<D> for ArenaIntervalTree<D> {
+    fn insert(&mut self, key: Range, data: D) { ArenaIntervalTree::insert(self, key, data) }
+
+    fn remove(&mut self, key: Range) -> Option<D> { ArenaIntervalTree::remove(self, key) }
+}
+
+impl<D> IntervalIndex<D> for FrozenIntervalTree<D> {
+    type RangeIter<'a> = FrozenRangePairIter<'a, D> where D: 'a;
+
+    fn range(&self, min: u64, max: u64) -> FrozenRangePairIter<'_, D> { FrozenIntervalTree::range(self, min, max) }
+
+    fn get(&self, key: Range) -> Option<&D> { FrozenIntervalTree::get(self, key) }
+
+    fn len(&self) -> usize { FrozenIntervalTree::len(self) }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::fmt::Debug;
+    use super::{IntervalIndex, IntervalIndexMut};
+    use ::{IntervalTree, ArenaIntervalTree, FrozenIntervalTree, CenteredIntervalTree, NCList, NaiveIntervalIndex, IntervalBTree};
+
+    type Entries = Vec<(memrange::Range, usize)>;
+
+    /// random intervals that hardly overlap
+    fn disjoint(len: usize) -> Entries {
+        let mut t = IntervalTree::<usize>::new();
+        for i in 0..len {
+            let offset = rand::random::<u64>() % (len as u64 * 100 + 1);
+            t.insert(memrange::Range::new(offset, offset + rand::random::<u64>() % 50), i);
+        }
+        t.iter().map(|(k, v)| (k, *v)).collect()
+    }
+
+    /// random intervals that are often nested several levels deep
+    fn nested(len: usize) -> Entries {
+        let mut t = IntervalTree::<usize>::new();
+        for i in 0..len {
+            let depth = rand::random::<u32>() % 12;
+            let offset = (rand::random::<u64>() % (1 << 12)) << depth;
+            t.insert(memrange::Range::new(offset, offset + (1 << depth) + rand::random::<u64>() % 2), i);
+        }
+        t.iter().map(|(k, v)| (k, *v)).collect()
+    }
+
+    /// compares the sorted results of index with a linear scan of entries
+    fn check_index<I: IntervalIndex<usize> + Debug>(index: &I, entries: &Entries) {
+        assert_eq!(index.len(), entries.len());
+        assert_eq!(index.is_empty(), entries.is_empty());
+        let mut all = index.range(0, u64::MAX).map(|(k, v)| (k, *v)).collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(&all, entries);
+        let span = entries.iter().map(|&(k, _)| k.max).max().unwrap_or(0) + 10;
+        for _ in 0..300 {
+            let min = rand::random::<u64>() % span;
+            let max = min + rand::random::<u64>() % (span / 8 + 1);
+            let query = memrange::Range::new(min, max);
+            let mut found = index.range(min, max).map(|(k, v)| (k, *v)).collect::<Vec<_>>();
+            found.sort();
+            let should = entries.iter().filter(|&(k, _)| k.intersect(&query)).cloned().collect::<Vec<_>>();
+            assert_eq!(found, should, "query {:?} on {:?}", query, index);
+            let stabbed = index.stab(min).count();
+            assert_eq!(stabbed, entries.iter().filter(|&(k, _)| k.min <= min && min <= k.max).count());
+        }
+        for &(k, v) in entries.iter().take(50) { assert_eq!(index.get(k), Some(&v)) }
+        assert_eq!(index.get(memrange::Range::new(span, span)), None);
+    }
+
+    fn check_all_backends(entries: Entries) {
+        check_index(&IntervalTree::<usize>::from_sorted(entries.clone()).unwrap(), &entries);
+        let mut arena = ArenaIntervalTree::new();
+        for &(k, v) in &entries { arena.insert(k, v) }
+        check_index(&arena, &entries);
+        let mut btree = IntervalBTree::new();
+        for &(k, v) in &entries { btree.insert(k, v) }
+        check_index(&btree, &entries);
+        check_index(&FrozenIntervalTree::from_sorted(entries.clone()).unwrap(), &entries);
+        check_index(&CenteredIntervalTree::from_sorted(entries.clone()).unwrap(), &entries);
+        check_index(&NCList::from_sorted(entries.clone()).unwrap(), &entries);
+        let mut naive = NaiveIntervalIndex::new();
+        for &(k, v) in &entries { naive.insert(k, v) }
+        check_index(&naive, &entries);
+    }
+
+    /// applies random updates to index and to a NaiveIntervalIndex and compares the results of
+    /// every step
+    fn check_against_naive<I: IntervalIndexMut<u64>>(mut index: I) {
+        let mut naive = NaiveIntervalIndex::new();
+        for i in 0..3000 {
+            let offset = rand::random::<u64>() % 500;
+            let key = memrange::Range::new(offset, offset + rand::random::<u64>() % 50);
+            if rand::random::<u8>() < 160 {
+                index.insert(key, i);
+                naive.insert(key, i);
+            } else {
+                assert_eq!(index.remove(key), naive.remove(key));
+            }
+            assert_eq!(index.len(), naive.len());
+            assert_eq!(index.get(key), naive.get(key));
+            let point = rand::random::<u64>() % 600;
+            let mut found = index.stab(point).collect::<Vec<_>>();
+            found.sort();
+            assert_eq!(found, naive.stab(point).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_differential(){
+        check_against_naive(IntervalTree::<u64>::new());
+        check_against_naive(ArenaIntervalTree::<u64>::new());
+        check_against_naive(IntervalBTree::<u64>::new());
+    }
+
+    #[test]
+    fn test_backends_disjoint(){
+        for &len in &[0, 1, 2, 10, 1000] { check_all_backends(disjoint(len)) }
+    }
+
+    #[test]
+    fn test_backends_nested(){
+        for &len in &[0, 1, 2, 10, 1000] { check_all_backends(nested(len)) }
+    }
+
+    /// checks that range_bounds answers like range on the closed bounds and yields nothing for
+    /// empty ones
+    macro_rules! check_range_bounds {
+        ($index:expr, $entries:expr) => {{
+            let (index, entries): (_, &Entries) = (&$index, &$entries);
+            let collect = |found: Vec<(memrange::Range, &usize)>| {
+                let mut found = found.into_iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>();
+                found.sort();
+                found
+            };
+            assert_eq!(collect(index.range_bounds(..).collect()), *entries);
+            for min in 0..22 {
+                assert_eq!(collect(index.range_bounds(min..min + 3).collect()), collect(index.range(min, min + 2).collect()));
+                assert_eq!(collect(index.range_bounds(..=min).collect()), collect(index.range(0, min).collect()));
+                assert_eq!(collect(index.range_bounds(min..).collect()), collect(index.range(min, u64::MAX).collect()));
+                assert_eq!(index.range_bounds(min..min).count(), 0);
+            }
+        }};
+    }
+
+    #[test]
+    fn test_range_bounds(){
+        let mut entries = Vec::new();
+        for min in 0..20 {
+            for max in min..20 { entries.push((memrange::Range::new(min, max), entries.len())) }
+        }
+        check_range_bounds!(CenteredIntervalTree::from_sorted(entries.clone()).unwrap(), entries);
+        check_range_bounds!(NCList::from_sorted(entries.clone()).unwrap(), entries);
+        let mut btree = IntervalBTree::new();
+        for &(k, v) in &entries { btree.insert(k, v) }
+        check_range_bounds!(btree, entries);
+    }
+
+    #[test]
+    fn test_backends_shared_bounds(){
+        let mut entries = Vec::new();
+        for min in 0..20 {
+            for max in min..20 { entries.push((memrange::Range::new(min, max), entries.len())) }
+        }
+        check_all_backends(entries);
+    }
+}
@@ -1,8 +1,7 @@
-extern crate memrange;
-
 use ::tree;
-use self::memrange::Range;
-use ::node::Node;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::node::{Node, NodeStore, Link, Owned};
 
 enum VisitingState {
     VisitLeft,
@@ -10,54 +9,103 @@ enum VisitingState {
     VisitRight
 }
 
-pub struct RangePairIter<'a,D:'a> {
-    range: Range,
-    stack: Vec<(&'a Node<D>, VisitingState)>
+/// Iterator over the (key,value) pairs of an `IntervalTree` that intersect a range, in key order.
+pub struct RangePairIter<'a,D:'a,S:'a = ()> {
+    inner: NodeRangeIter<'a, D, S, Owned>
 }
 
 
-impl<'a, D:'a> RangePairIter<'a, D> {
+impl<'a, D:'a, S:'a> RangePairIter<'a, D, S> {
 
-    pub fn new(tree: &'a tree::IntervalTree<D>, lower: u64, upper: u64) -> RangePairIter<'a,D>{
-        let mut stack = Vec::with_capacity(tree.height());
-        if let Some(ref root) = tree.root {
-            stack.push( (&**root,VisitingState::VisitLeft) );
-        }
-        //RangePairIter{tree: tree, range: Range::new(lower,upper), stack: stack}
-        RangePairIter{ range: Range::new(lower,upper), stack: stack}
+    pub fn new<O: ?Sized>(tree: &'a tree::IntervalTree<D, S, O>, lower: u64, upper: u64) -> RangePairIter<'a,D,S>{
+        RangePairIter::from_root(tree.root.as_deref(), lower, upper)
+    }
+
+    /// an iterator over the pairs of the tree below root that intersect the two bounds
+    pub(crate) fn from_root(root: Option<&'a Node<D, S>>, lower: u64, upper: u64) -> RangePairIter<'a,D,S>{
+        RangePairIter{ inner: NodeRangeIter::from_root(&(), root, lower, upper) }
+    }
+
+    /// an iterator that yields nothing, e.g. for queries with empty bounds
+    pub(crate) fn empty() -> RangePairIter<'a,D,S>{
+        RangePairIter{ inner: NodeRangeIter::from_root(&(), None, 0, 0) }
+    }
+
+    pub fn visit_left(&mut self, node: &'a Node<D, S>) {
+        self.inner.visit_left(node)
+    }
+
+    pub fn visit_right(&mut self, node: &'a Node<D, S>) {
+        self.inner.visit_right(node)
+    }
+
+    pub fn visit_center(&mut self, node: &'a Node<D, S>) -> Option<&'a Node<D, S>>{
+        self.inner.visit_center(node)
+    }
+
+    pub fn get_next_node(&mut self) -> Option<&'a Node<D, S>>{
+        self.inner.get_next_node()
+    }
+}
+
+impl<'a, D:'a, S:'a> Iterator for RangePairIter<'a, D, S> {
+
+    type Item = (Range,&'a D);
+
+    fn next(&mut self) -> Option<(Range,&'a D)> {
+        self.inner.next()
+    }
+}
+
+/// The traversal behind `RangePairIter`, for trees with any kind of `Link` between their nodes.
+/// The public iterators wrap it, so the link type never shows up in their signatures.
+pub(crate) struct NodeRangeIter<'a,D:'a,S:'a,L:'a + Link> {
+    store: &'a NodeStore<D, S, L>,
+    range: Range,
+    stack: Vec<(&'a Node<D, S, L>, VisitingState)>
+}
+
+impl<'a, D:'a, S:'a, L:'a + Link> NodeRangeIter<'a, D, S, L> {
+
+    /// an iterator over the pairs of the tree below root, with its nodes in store, that intersect
+    /// the two bounds
+    pub(crate) fn from_root(store: &'a NodeStore<D, S, L>, root: Option<&'a Node<D, S, L>>, lower: u64, upper: u64) -> NodeRangeIter<'a,D,S,L>{
+        let mut stack = Vec::with_capacity(root.map_or(0, |root| root.height as usize));
+        stack.extend(root.map(|root| (root, VisitingState::VisitLeft)));
+        NodeRangeIter{ store, range: Range::new(lower,upper), stack }
     }
 
-    pub fn visit_left(&mut self, node: &'a Node<D>) {
+    fn visit_left(&mut self, node: &'a Node<D, S, L>) {
         //println!("left {:?}", node.key);
         match node.left {
             Some(ref lsucc) => {
                 self.stack.push( (node, VisitingState::VisitCenter) );
                 if node.left_subtree_relevant(&self.range) { 
-                    self.stack.push( (&**lsucc, VisitingState::VisitLeft) ) 
+                    self.stack.push( (L::get(self.store, lsucc), VisitingState::VisitLeft) ) 
                 }
             },
             None => self.stack.push( (node, VisitingState::VisitCenter) )
         }
     }
 
-    pub fn visit_right(&mut self, node: &'a Node<D>) {
+    fn visit_right(&mut self, node: &'a Node<D, S, L>) {
         //println!("right {:?}", node.key);
         if !node.right_subtree_relevant(&self.range) { return }
         match node.right {
             Some(ref rsucc) => {
-                self.stack.push((&**rsucc, VisitingState::VisitLeft));
+                self.stack.push((L::get(self.store, rsucc), VisitingState::VisitLeft));
             },
             None => return
         }
     }
 
-    pub fn visit_center(&mut self, node: &'a Node<D>) -> Option<&'a Node<D>>{
+    fn visit_center(&mut self, node: &'a Node<D, S, L>) -> Option<&'a Node<D, S, L>>{
         //println!("center {:?}", node.key);
         self.stack.push((node, VisitingState::VisitRight));
         if node.key.intersect(&self.range){ return Some(node) } else { return self.get_next_node() }
     }
 
-    pub fn get_next_node(&mut self) -> Option<&'a Node<D>>{
+    fn get_next_node(&mut self) -> Option<&'a Node<D, S, L>>{
         if let Some((node, state)) = self.stack.pop() {
             match state {
                 VisitingState::VisitLeft => {self.visit_left(node); return self.get_next_node()},
@@ -70,7 +118,7 @@ impl<'a, D:'a> RangePairIter<'a, D> {
     }
 }
 
-impl<'a, D:'a> Iterator for RangePairIter<'a, D> {
+impl<'a, D:'a, S:'a, L:'a + Link> Iterator for NodeRangeIter<'a, D, S, L> {
 
     type Item = (Range,&'a D);
 
@@ -79,6 +127,35 @@ impl<'a, D:'a> Iterator for RangePairIter<'a, D> {
     }
 }
 
+/// Iterator over the (key,value) pairs intersecting a range, that additionally yields each key
+/// clipped to the queried range.
+pub struct ClippedRangePairIter<'a,D:'a,S:'a = ()> {
+    inner: RangePairIter<'a, D, S>
+}
+
+impl<'a, D:'a, S:'a> ClippedRangePairIter<'a, D, S> {
+
+    pub fn new<O: ?Sized>(tree: &'a tree::IntervalTree<D, S, O>, lower: u64, upper: u64) -> ClippedRangePairIter<'a,D,S>{
+        ClippedRangePairIter{ inner: RangePairIter::new(tree, lower, upper) }
+    }
+
+    /// an iterator that yields nothing, e.g. for queries with empty bounds
+    pub(crate) fn empty() -> ClippedRangePairIter<'a,D,S>{
+        ClippedRangePairIter{ inner: RangePairIter::empty() }
+    }
+}
+
+impl<'a, D:'a, S:'a> Iterator for ClippedRangePairIter<'a, D, S> {
+
+    type Item = (Range,Range,&'a D);
+
+    fn next(&mut self) -> Option<(Range,Range,&'a D)> {
+        let range = self.inner.inner.range;
+        self.inner.next().map(|(key, data)| (key, key.get_intersection(&range), data))
+    }
+}
+
+#[cfg(feature = "std")]
 #[test]
 fn test_iterators(){
     let mut tree = tree::IntervalTree::<i32>::new();
@@ -111,3 +188,20 @@ fn test_iterators(){
     assert_eq!(iter.next().expect("should have a few values").0, Range::new(17,17));
     assert!(iter.next().is_none());
 }
+
+#[cfg(feature = "std")]
+#[test]
+fn test_clipped_iterator(){
+    let mut tree = tree::IntervalTree::<i32>::new();
+    tree.insert(Range::new(0,10), 1);
+    tree.insert(Range::new(5,6), 2);
+    tree.insert(Range::new(8,30), 3);
+    tree.insert(Range::new(40,50), 4);
+
+    let clipped = ClippedRangePairIter::new(&tree, 6, 20).collect::<Vec<_>>();
+    assert_eq!(clipped, vec![
+        (Range::new(0,10), Range::new(6,10), &1),
+        (Range::new(5,6), Range::new(6,6), &2),
+        (Range::new(8,30), Range::new(8,20), &3)]);
+    assert!(ClippedRangePairIter::new(&tree, 31, 39).next().is_none());
+}
@@ -1,9 +1,66 @@
-#[cfg(test)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(all(test, feature = "std"))]
 extern crate rand;
+#[cfg(feature = "std")]
 extern crate memrange;
+#[macro_use]
+extern crate alloc;
+// no_std builds get core from the compiler
+#[cfg(feature = "std")]
+extern crate core;
 
+mod range;
 mod node;
 pub mod tree;
 mod iterators;
+mod flat;
+mod arena;
+mod statictree;
+mod frozen;
+mod index;
+mod centered;
+mod nclist;
+mod naive;
+mod btree;
+mod observer;
+mod bounds;
+mod error;
+mod summary;
+mod depth;
+mod topk;
+mod batch;
+mod visit;
+mod set;
+mod boxtree;
+#[cfg(feature = "std")]
+mod concurrent;
+#[cfg(feature = "rcu")]
+mod rcu;
+mod bulk;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub use range::Range;
 pub use tree::IntervalTree;
-pub use iterators::RangePairIter;
+pub use iterators::{RangePairIter, ClippedRangePairIter};
+pub use node::{NodeRef, InvariantError};
+pub use flat::{FlatTree, FlatError, FlatRangeIter};
+pub use arena::{ArenaIntervalTree, ArenaRangePairIter};
+pub use statictree::{StaticIntervalTree, StaticRangePairIter};
+pub use frozen::{FrozenIntervalTree, FrozenRangePairIter};
+pub use index::{IntervalIndex, IntervalIndexMut};
+pub use centered::{CenteredIntervalTree, CenteredRangePairIter};
+pub use nclist::{NCList, NCListRangePairIter};
+pub use naive::{NaiveIntervalIndex, NaiveRangePairIter};
+pub use btree::{IntervalBTree, BTreeRangePairIter};
+pub use observer::TreeObserver;
+pub use bounds::{closed_range, Bounds, BoundsIntervalTree, BoundsRangeIter};
+pub use error::{Error, OnDuplicate};
+pub use summary::Summary;
+pub use batch::{BatchQueryIter, PointQueries, RangeQueries, BoundsQueries};
+pub use set::{IntervalSet, IntervalSetIter};
+pub use boxtree::{BoxTree, BoundingBox};
+#[cfg(feature = "std")]
+pub use concurrent::ConcurrentIntervalTree;
+#[cfg(feature = "rcu")]
+pub use rcu::{RcuIntervalTree, RcuSnapshot, RcuRangeIter};
@@ -0,0 +1,113 @@
+use core::slice;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::index::{IntervalIndex, IntervalIndexMut};
+
+/// A brute force interval index: a `Vec` of all entries sorted by key, every query scans all of
+/// them. It is obviously correct, which makes it the reference for differential tests of the other
+/// indexes, and for a handful of entries it is also hard to beat.
+#[derive(Debug, Clone)]
+pub struct NaiveIntervalIndex<D> {
+    entries: Vec<(Range, D)>,
+}
+
+impl<D> Default for NaiveIntervalIndex<D> {
+    fn default() -> NaiveIntervalIndex<D> {
+        NaiveIntervalIndex::new()
+    }
+}
+
+impl<D> NaiveIntervalIndex<D> {
+
+/// This function will construct a new empty NaiveIntervalIndex.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::NaiveIntervalIndex::<i32>::new();
+/// t.insert(memrange::Range::new(3,8),1);
+/// t.insert(memrange::Range::new(12,36),2);
+/// assert_eq!(t.range(9,14).map(|(k,_)| k.min).collect::<Vec<u64>>(), vec![12]);
+/// ```
+    pub fn new() -> NaiveIntervalIndex<D> {
+        NaiveIntervalIndex { entries: Vec::new() }
+    }
+
+/// This function will insert the key,value pair, overwriting the old data if the key is allready
+/// part of the index.
+    pub fn insert(&mut self, key: Range, data: D) {
+        match self.entries.binary_search_by(|entry| entry.0.cmp(&key)) {
+            Ok(i) => self.entries[i].1 = data,
+            Err(i) => self.entries.insert(i, (key, data)),
+        }
+    }
+
+/// This function will remove the key,value pair and return the data, or None if the key is not
+/// part of the index.
+    pub fn remove(&mut self, key: Range) -> Option<D> {
+        let i = self.entries.binary_search_by(|entry| entry.0.cmp(&key)).ok()?;
+        Some(self.entries.remove(i).1)
+    }
+
+/// This function will return the Some(data) stored under the given key or None if the key is not
+/// known.
+    pub fn get(&self, key: Range) -> Option<&D> {
+        let i = self.entries.binary_search_by(|entry| entry.0.cmp(&key)).ok()?;
+        Some(&self.entries[i].1)
+    }
+
+/// This function will return the number of key,value pairs in the index.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+/// This function will return true if the index is empty, false otherwise.
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+/// This function will return a read only iterator for all (key,value) pairs in the index.
+    pub fn iter(&self) -> NaiveRangePairIter<'_, D> {
+        self.range(0, 0xffff_ffff_ffff_ffff)
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs between the two
+/// bounds, in key order.
+    pub fn range(&self, min: u64, max: u64) -> NaiveRangePairIter<'_, D> {
+        NaiveRangePairIter { entries: self.entries.iter(), range: Range::new(min, max) }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs containing the point.
+    pub fn stab(&self, point: u64) -> NaiveRangePairIter<'_, D> {
+        self.range(point, point)
+    }
+}
+
+impl<D> IntervalIndex<D> for NaiveIntervalIndex<D> {
+    type RangeIter<'a> = NaiveRangePairIter<'a, D> where D: 'a;
+
+    fn range(&self, min: u64, max: u64) -> NaiveRangePairIter<'_, D> { NaiveIntervalIndex::range(self, min, max) }
+
+    fn get(&self, key: Range) -> Option<&D> { NaiveIntervalIndex::get(self, key) }
+
+    fn len(&self) -> usize { NaiveIntervalIndex::len(self) }
+}
+
+impl<D> IntervalIndexMut<D> for NaiveIntervalIndex<D> {
+    fn insert(&mut self, key: Range, data: D) { NaiveIntervalIndex::insert(self, key, data) }
+
+    fn remove(&mut self, key: Range) -> Option<D> { NaiveIntervalIndex::remove(self, key) }
+}
+
+/// Iterator over the entries of a `NaiveIntervalIndex` that intersect a range.
+pub struct NaiveRangePairIter<'a, D: 'a> {
+    entries: slice::Iter<'a, (Range, D)>,
+    range: Range,
+}
+
+impl<'a, D: 'a> Iterator for NaiveRangePairIter<'a, D> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        let range = self.range;
+        self.entries.by_ref().find(|entry| entry.0.intersect(&range)).map(|entry| (entry.0, &entry.1))
+    }
+}
@@ -0,0 +1,167 @@
+use core::cmp::Reverse;
+use core::ops::RangeBounds;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::bounds::closed_range;
+use ::node::into_sorted;
+use ::tree::IntervalTree;
+use ::index::IntervalIndex;
+use ::error::{Error, check_sorted};
+
+/// A static nested containment list (NCList, Alekseyenko and Lee 2007). Intervals that are not
+/// contained in any other interval form the top level list, the intervals directly contained in an
+/// interval form its sublist. No interval of a list contains another one, so every list is sorted
+/// by `min` and by `max` at the same time and the first intersecting interval can be found by
+/// binary search. All lists are stored in one array, the sublist of the entry at `i` is
+/// `sublists[i]`.
+#[derive(Debug, Clone)]
+pub struct NCList<D> {
+    keys: Vec<Range>,
+    sublists: Vec<(usize, usize)>,
+    data: Vec<D>,
+    /// the length of the top level list, which starts at 0
+    top_len: usize,
+}
+
+impl<D> NCList<D> {
+
+    fn from_entries(entries: Vec<(Range, D)>) -> NCList<D> {
+        let len = entries.len();
+        // with this order every interval comes after all intervals containing it
+        let mut order = (0..len).collect::<Vec<_>>();
+        order.sort_by_key(|&i| (entries[i].0.min, Reverse(entries[i].0.max)));
+        // children[len] is the top level list
+        let mut children = vec![Vec::new(); len + 1];
+        let mut containing: Vec<usize> = Vec::new();
+        for i in order {
+            let key = entries[i].0;
+            while containing.last().is_some_and(|&c| entries[c].0.max < key.max) { containing.pop(); }
+            children[containing.last().cloned().unwrap_or(len)].push(i);
+            containing.push(i);
+        }
+        // lay out the lists breadth first: every list is appended once its owner is placed
+        let mut layout = children[len].clone();
+        let top_len = layout.len();
+        let mut sublists = Vec::with_capacity(len);
+        let mut pos = 0;
+        while pos < layout.len() {
+            let start = layout.len();
+            layout.extend_from_slice(&children[layout[pos]]);
+            sublists.push((start, layout.len()));
+            pos += 1;
+        }
+        let mut slots = entries.into_iter().map(Some).collect::<Vec<_>>();
+        let (keys, data) = layout.iter().map(|&i| slots[i].take().expect("every entry is placed once")).unzip();
+        NCList { keys, sublists, data, top_len }
+    }
+
+/// This function will build a nested containment list from entries that are sorted by key without
+/// duplicates, e.g. the output of `IntervalTree::iter`. If a key is not bigger than the one before
+/// it, `Error::Unsorted` is returned.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::NCList;
+///
+/// let l = NCList::from_sorted(vec![(Range::new(1,50),1), (Range::new(2,3),2), (Range::new(7,9),3)]).unwrap();
+/// assert_eq!(l.range(4,8).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1,3]);
+/// ```
+    pub fn from_sorted<I: IntoIterator<Item=(Range, D)>>(entries: I) -> Result<NCList<D>, Error> {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        check_sorted(entries.iter().map(|(key, _)| key))?;
+        Ok(NCList::from_entries(entries))
+    }
+
+/// This function will return the number of key,value pairs in the list.
+    pub fn len(&self) -> usize { self.keys.len() }
+
+/// This function will return true if the list is empty, false otherwise.
+    pub fn is_empty(&self) -> bool { self.keys.is_empty() }
+
+    /// the position of the first interval of the list start..end that ends at or after min
+    fn first_ending_after(&self, start: usize, end: usize, min: u64) -> usize {
+        start + self.keys[start..end].partition_point(|key| key.max < min)
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs between the two
+/// bounds. Every interval is followed by the intersecting intervals it contains, so the pairs are
+/// not returned in key order.
+    pub fn range(&self, min: u64, max: u64) -> NCListRangePairIter<'_, D> {
+        let first = self.first_ending_after(0, self.top_len, min);
+        NCListRangePairIter { list: self, range: Range::new(min, max), stack: vec![(first, self.top_len)] }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs intersecting the given
+/// bounds, which may be inclusive, exclusive or unbounded on either side. Empty bounds yield
+/// nothing.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::NCList;
+///
+/// let l = NCList::from_sorted(vec![(Range::new(1,10),1), (Range::new(2,3),2), (Range::new(5,8),3)]).unwrap();
+/// assert_eq!(l.range_bounds(..2).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1]);
+/// assert_eq!(l.range_bounds(3..=5).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1,2,3]);
+/// assert_eq!(l.range_bounds(4..4).count(), 0);
+/// ```
+    pub fn range_bounds<R: RangeBounds<u64>>(&self, bounds: R) -> NCListRangePairIter<'_, D> {
+        match closed_range(&bounds) {
+            Some(range) => self.range(range.min, range.max),
+            None => NCListRangePairIter { list: self, range: Range::new(0, 0), stack: Vec::new() },
+        }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs containing the point.
+    pub fn stab(&self, point: u64) -> NCListRangePairIter<'_, D> {
+        self.range(point, point)
+    }
+}
+
+impl<D, O: ?Sized> From<IntervalTree<D, (), O>> for NCList<D> {
+    fn from(mut tree: IntervalTree<D, (), O>) -> NCList<D> {
+        NCList::from_entries(into_sorted(tree.root.take()))
+    }
+}
+
+impl<D> IntervalIndex<D> for NCList<D> {
+    type RangeIter<'a> = NCListRangePairIter<'a, D> where D: 'a;
+
+    fn range(&self, min: u64, max: u64) -> NCListRangePairIter<'_, D> { NCList::range(self, min, max) }
+
+    fn len(&self) -> usize { NCList::len(self) }
+}
+
+/// Iterator over the entries of an `NCList` that intersect a range. The stack holds the remaining
+/// part of every list that is currently visited, the first of them ends at or after the range.
+pub struct NCListRangePairIter<'a, D: 'a> {
+    list: &'a NCList<D>,
+    range: Range,
+    stack: Vec<(usize, usize)>,
+}
+
+impl<'a, D: 'a> Iterator for NCListRangePairIter<'a, D> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        loop {
+            let (pos, end) = *self.stack.last()?;
+            if pos >= end || self.list.keys[pos].min > self.range.max {
+                self.stack.pop();
+                continue
+            }
+            let key = self.list.keys[pos];
+            self.stack.last_mut().expect("stack is not empty").0 += 1;
+            let (start, end) = self.list.sublists[pos];
+            if start < end {
+                let first = self.list.first_ending_after(start, end, self.range.min);
+                self.stack.push((first, end));
+            }
+            return Some((key, &self.list.data[pos]))
+        }
+    }
+}
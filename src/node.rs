@@ -1,22 +1,197 @@
-extern crate memrange;
-use std::cmp;
-use std::cmp::Ordering;
-use self::memrange::Range;
+use core::cmp;
+use core::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::error;
+use core::fmt;
+use core::num::NonZeroU32;
+use alloc::boxed::Box;
+#[cfg(feature = "rcu")]
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::summary::Summary;
 
+mod sealed {
+    /// Keeps `Link` closed to the link types of this crate.
+    pub trait Sealed {}
+}
+
+/// How the nodes of a tree refer to their successors. `Owned` puts every node in its own `Box`,
+/// `Shared` puts them in `Arc`s so that the versions of a persistent tree can share untouched
+/// subtrees, and `Indexed` keeps them in the slots of an array. All operations of this module work
+/// with each of them.
+pub trait Link: sealed::Sealed {
+    /// the pointer from a node to one of its successors
+    type Ptr<N>;
+
+    /// what the pointers point into: nothing for `Owned` and `Shared`, the slots for `Indexed`
+    type Store<N>: ?Sized;
+
+    /// returns the node behind ptr
+    fn get<'a, N>(store: &'a Self::Store<N>, ptr: &'a Self::Ptr<N>) -> &'a N;
+}
+
+/// Mutable access to the nodes behind a `Link`, as needed by the operations that change a tree.
+pub trait LinkMut<N>: Link {
+    /// returns the node for modification. A node that is shared with another tree is copied first,
+    /// so the other tree never sees the change.
+    fn make_mut<'a>(store: &'a mut Self::Store<N>, ptr: &'a mut Self::Ptr<N>) -> &'a mut N;
+}
+
+/// Successors in a `Box`, for trees with a single owner like `IntervalTree`.
+#[derive(Debug, Clone, Copy)]
+pub struct Owned;
+
+impl sealed::Sealed for Owned {}
+
+impl Link for Owned {
+    type Ptr<N> = Box<N>;
+    type Store<N> = ();
+
+    fn get<'a, N>(_: &'a (), ptr: &'a Box<N>) -> &'a N { ptr }
+}
+
+impl<N> LinkMut<N> for Owned {
+    fn make_mut<'a>(_: &'a mut (), ptr: &'a mut Box<N>) -> &'a mut N { ptr }
+}
+
+/// Successors in an `Arc`, for the versions of an `RcuIntervalTree`. Changing such a tree copies the
+/// nodes on the path to the change and shares all other nodes with the older versions.
+#[cfg(feature = "rcu")]
+#[derive(Debug, Clone, Copy)]
+pub struct Shared;
+
+#[cfg(feature = "rcu")]
+impl sealed::Sealed for Shared {}
+
+#[cfg(feature = "rcu")]
+impl Link for Shared {
+    type Ptr<N> = Arc<N>;
+    type Store<N> = ();
+
+    fn get<'a, N>(_: &'a (), ptr: &'a Arc<N>) -> &'a N { ptr }
+}
+
+#[cfg(feature = "rcu")]
+impl<N: Clone> LinkMut<N> for Shared {
+    fn make_mut<'a>(_: &'a mut (), ptr: &'a mut Arc<N>) -> &'a mut N { Arc::make_mut(ptr) }
+}
+
+/// The position of a node in the slots of an `Indexed` tree. It is stored plus one, so that an
+/// `Option<Index>` is no bigger than an `u32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Index(NonZeroU32);
+
+impl Index {
+    /// the index of the slot at position, which has to be smaller than `u32::MAX`
+    pub fn new(position: usize) -> Index {
+        debug_assert!(position < u32::MAX as usize);
+        Index(NonZeroU32::MIN.saturating_add(position as u32))
+    }
+
+    pub fn position(self) -> usize {
+        (self.0.get() - 1) as usize
+    }
+}
+
+/// A slot of the array behind an `Indexed` tree: either a node or a link in the list of free slots.
 #[derive(Debug)]
-pub struct Node<D> {
-    pub key: Range,
-    pub data: D,
-    height: u32,
+pub enum Slot<N> {
+    Occupied(N),
+    Vacant(Option<Index>),
+}
+
+/// Successors in the slots of an array, linked by their `Index`, for `ArenaIntervalTree` and
+/// `StaticIntervalTree`. The operations of this module never allocate or free a slot themselves.
+#[derive(Debug, Clone, Copy)]
+pub struct Indexed;
+
+impl sealed::Sealed for Indexed {}
+
+impl Link for Indexed {
+    type Ptr<N> = Index;
+    type Store<N> = [Slot<N>];
+
+    fn get<'a, N>(slots: &'a [Slot<N>], index: &'a Index) -> &'a N {
+        match slots[index.position()] {
+            Slot::Occupied(ref node) => node,
+            Slot::Vacant(_) => panic!("Arena broken"),
+        }
+    }
+}
+
+impl<N> LinkMut<N> for Indexed {
+    fn make_mut<'a>(slots: &'a mut [Slot<N>], index: &'a mut Index) -> &'a mut N {
+        match slots[index.position()] {
+            Slot::Occupied(ref mut node) => node,
+            Slot::Vacant(_) => panic!("Arena broken"),
+        }
+    }
+}
+
+/// The pointer from a node to one of its successors.
+pub type NodePtr<D, S, L> = <L as Link>::Ptr<Node<D, S, L>>;
+
+/// A pointer that may be missing, like an empty subtree or a node that was not found.
+pub type OptNodePtr<D, S, L> = Option<NodePtr<D, S, L>>;
+
+/// The nodes a `NodePtr` points into.
+pub type NodeStore<D, S, L> = <L as Link>::Store<Node<D, S, L>>;
+
+pub struct Node<D, S = (), L: Link = Owned> {
+    pub(crate) key: Range,
+    pub(crate) data: D,
+    pub(crate) height: u32,
     max: u64,
-    pub left: Option<Box<Node<D>>>,
-    pub right:Option<Box<Node<D>>>,
+    max_span: u64,
+    pub(crate) summary: S,
+    pub(crate) left: Option<NodePtr<D, S, L>>,
+    pub(crate) right:Option<NodePtr<D, S, L>>,
 }
 
-impl<D> Node<D> {
-    pub fn new(key: Range, data: D) -> Node<D>{
-        Node::<D>{key: key, data: data, height: 1, max: key.max, left: None, right: None}
+impl<D: fmt::Debug, S: fmt::Debug> fmt::Debug for Node<D, S, Owned> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_node(self, f)
     }
+}
+
+#[cfg(feature = "rcu")]
+impl<D: fmt::Debug, S: fmt::Debug> fmt::Debug for Node<D, S, Shared> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_node(self, f)
+    }
+}
+
+impl<D: fmt::Debug, S: fmt::Debug> fmt::Debug for Node<D, S, Indexed> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt_node(self, f)
+    }
+}
+
+/// formats the fields of node, with its successors as they are printed by the link type
+fn fmt_node<D: fmt::Debug, S: fmt::Debug, L: Link>(node: &Node<D, S, L>, f: &mut fmt::Formatter) -> fmt::Result
+    where NodePtr<D, S, L>: fmt::Debug {
+    f.debug_struct("Node").field("key", &node.key).field("data", &node.data).field("height", &node.height)
+        .field("max", &node.max).field("max_span", &node.max_span).field("summary", &node.summary)
+        .field("left", &node.left).field("right", &node.right).finish()
+}
+
+#[cfg(feature = "rcu")]
+impl<D: Clone, S: Clone> Clone for Node<D, S, Shared> {
+    fn clone(&self) -> Node<D, S, Shared> {
+        Node { key: self.key, data: self.data.clone(), height: self.height, max: self.max, max_span: self.max_span,
+            summary: self.summary.clone(), left: self.left.clone(), right: self.right.clone() }
+    }
+}
+
+impl<D, S: Summary<D>, L: Link> Node<D, S, L> {
+    pub fn new(key: Range, data: D) -> Node<D, S, L>{
+        let summary = S::from_entry(&key, &data);
+        Node::<D, S, L>{key: key, data: data, height: 1, max: key.max, max_span: key.max-key.min, summary: summary, left: None, right: None}
+    }
+}
+
+impl<D, S, L: Link> Node<D, S, L> {
 
     pub fn right_subtree_relevant(&self, range: &Range) -> bool{
         return range.max >= self.key.min
@@ -25,137 +200,310 @@ impl<D> Node<D> {
     pub fn left_subtree_relevant(&self, range: &Range) -> bool{
         return self.max >= range.min
     }
+
+    /// the biggest `key.max-key.min` in the subtree of this node, bounding the length of its keys
+    pub fn max_span(&self) -> u64 {
+        self.max_span
+    }
+
+    /// the left and the right successor of this node, looked up in store
+    pub(crate) fn successors<'a>(&'a self, store: &'a NodeStore<D, S, L>) -> (Option<&'a Self>, Option<&'a Self>) {
+        (self.left.as_ref().map(|ptr| L::get(store, ptr)), self.right.as_ref().map(|ptr| L::get(store, ptr)))
+    }
+}
+
+pub fn height<D, S>(node: &Option<Box<Node<D, S>>>) -> u32  {
+    node_height(node.as_deref())
+}
+
+pub(crate) fn node_height<D, S, L: Link>(node: Option<&Node<D, S, L>>) -> u32 {
+    node.map_or(0, |succ| succ.height)
+}
+
+fn subtree_max<D, S, L: Link>(node: Option<&Node<D, S, L>>) -> u64 {
+    node.map_or(0, |succ| succ.max)
+}
+
+/// returns the biggest `key.max-key.min` of all keys in the subtree (that is their length - 1)
+fn subtree_max_span<D, S, L: Link>(node: Option<&Node<D, S, L>>) -> u64 {
+    node.map_or(0, |succ| succ.max_span)
 }
 
-pub fn height<D>(node: &Option<Box<Node<D>>>) -> u32  {
-    return node.as_ref().map_or(0, |succ| succ.height)
+/// the summary of the entry of node, combined with the cached summaries of its successors left
+/// and right.
+fn combined_summary<D, S: Summary<D>, L: Link>(node: &Node<D, S, L>, left: Option<&Node<D, S, L>>, right: Option<&Node<D, S, L>>) -> S {
+    let own = S::from_entry(&node.key, &node.data);
+    let with_left = match left { Some(left) => left.summary.combine(&own), None => own };
+    match right { Some(right) => with_left.combine(&right.summary), None => with_left }
 }
 
+/// The side of its parent on which a node on a search path was detached.
+enum Side {
+    Left,
+    Right
+}
+
+/// The maximal height of an AVL tree that fits into memory. A tree of height h has more than
+/// 1.618^(h-1) nodes, so 1.5 times the number of address bits can never be reached.
+const MAX_HEIGHT: usize = usize::BITS as usize * 3 / 2;
+
+/// The nodes on the way from the root to a change, each with the side its successor on the path was
+/// detached from. The path lives on the stack, so changing a tree allocates at most the new node.
+struct Path<P> {
+    entries: [Option<(P, Side)>; MAX_HEIGHT],
+    len: usize,
+}
+
+impl<P> Path<P> {
+    fn new() -> Path<P> {
+        Path { entries: [const { None }; MAX_HEIGHT], len: 0 }
+    }
+
+    fn push(&mut self, ptr: P, side: Side) {
+        self.entries[self.len] = Some((ptr, side));
+        self.len += 1;
+    }
 
-fn subtree_max<D>(node: &Option<Box<Node<D>>>) -> u64 {
-    return node.as_ref().map_or(0, |succ| succ.max)
+    fn pop(&mut self) -> Option<(P, Side)> {
+        if self.len == 0 { return None }
+        self.len -= 1;
+        self.entries[self.len].take()
+    }
+}
+
+// The operations that change a tree. They take and return the pointers to the (sub) trees they
+// change and modify the nodes through `LinkMut::make_mut`, so for `Shared` links every node on the
+// path to a change is copied while the old version of the tree stays untouched. They never hold
+// two nodes at once, as `Indexed` nodes are borrowed from the same store.
+impl<D, S: Summary<D>, L: LinkMut<Node<D, S, L>>> Node<D, S, L> {
+
+/// update the cached values of root. To call this function make sure that the cached values of
+/// both children of root ar up to date.
+fn update_height(store: &mut NodeStore<D, S, L>, root: &mut NodePtr<D, S, L>){
+    let (height, max, max_span, summary) = {
+        let node = L::get(store, root);
+        let (left, right) = node.successors(store);
+        (cmp::max( node_height(left), node_height(right) )+1,
+         cmp::max(subtree_max(left), cmp::max(subtree_max(right), node.key.max)),
+         cmp::max(subtree_max_span(left), cmp::max(subtree_max_span(right), node.key.max-node.key.min)),
+         combined_summary(node, left, right))
+    };
+    let node = L::make_mut(store, root);
+    node.height = height;
+    node.max = max;
+    node.max_span = max_span;
+    node.summary = summary;
 }
 
 /// Perform a single right rotation on this (sub) tree
-fn rotate_right<D>(mut root: Box<Node<D>>) -> Box<Node<D>>{
-    let mut new_root_box = root.left.take().expect("Avl broken");
-    root.left = new_root_box.right.take();
-    update_height(&mut root);
-    new_root_box.right = Some(root);
-    update_height(&mut new_root_box);
-    return new_root_box
+fn rotate_right(store: &mut NodeStore<D, S, L>, mut root: NodePtr<D, S, L>) -> NodePtr<D, S, L>{
+    let mut new_root = L::make_mut(store, &mut root).left.take().expect("Avl broken");
+    let moved = L::make_mut(store, &mut new_root).right.take();
+    L::make_mut(store, &mut root).left = moved;
+    Self::update_height(store, &mut root);
+    L::make_mut(store, &mut new_root).right = Some(root);
+    Self::update_height(store, &mut new_root);
+    return new_root
 }
 
 /// Perform a single left rotation on this (sub) tree
-fn rotate_left<D>(mut root: Box<Node<D>>) -> Box<Node<D>>{
-    let mut new_root_box = root.right.take().expect("Avl broken");
-    root.right = new_root_box.left.take();
-    update_height(&mut root);
-    new_root_box.left = Some(root);
-    update_height(&mut new_root_box);
-    return new_root_box
+fn rotate_left(store: &mut NodeStore<D, S, L>, mut root: NodePtr<D, S, L>) -> NodePtr<D, S, L>{
+    let mut new_root = L::make_mut(store, &mut root).right.take().expect("Avl broken");
+    let moved = L::make_mut(store, &mut new_root).left.take();
+    L::make_mut(store, &mut root).right = moved;
+    Self::update_height(store, &mut root);
+    L::make_mut(store, &mut new_root).left = Some(root);
+    Self::update_height(store, &mut new_root);
+    return new_root
+}
+
+/// returns the difference between the heights of the left and the right successor of root
+fn balance(store: &NodeStore<D, S, L>, root: &NodePtr<D, S, L>) -> i32 {
+    let (left, right) = L::get(store, root).successors(store);
+    (node_height(left) as i32) - (node_height(right) as i32)
 }
 
 /// Performs a rotation that counteracts the fact that the left successor is too high
-fn rotate_left_successor<D>(mut root: Box<Node<D>>) -> Box<Node<D>> {
-    let left = root.left.take().expect("Interval broken");
-    if height(&left.left) < height(&left.right) {
-        let rotated = rotate_left(left);
-        root.left = Some(rotated);
-        update_height(&mut root);
-    }
-    else{
-        root.left = Some(left);
+fn rotate_left_successor(store: &mut NodeStore<D, S, L>, mut root: NodePtr<D, S, L>) -> NodePtr<D, S, L> {
+    let mut left = L::make_mut(store, &mut root).left.take().expect("Interval broken");
+    if Self::balance(store, &left) < 0 {
+        left = Self::rotate_left(store, left);
     }
-    rotate_right(root)
+    L::make_mut(store, &mut root).left = Some(left);
+    Self::rotate_right(store, root)
 }
 
 /// Performs a rotation that counteracts the fact that the right successor is too high
-fn rotate_right_successor<D>(mut root: Box<Node<D>>) -> Box<Node<D>> {
-    let right = root.right.take().expect("Interval broken");
-    if height(&right.left) > height(&right.right) {
-        let rotated = rotate_right(right);
-        root.right = Some(rotated);
-        update_height(&mut root);
+fn rotate_right_successor(store: &mut NodeStore<D, S, L>, mut root: NodePtr<D, S, L>) -> NodePtr<D, S, L> {
+    let mut right = L::make_mut(store, &mut root).right.take().expect("Interval broken");
+    if Self::balance(store, &right) > 0 {
+        right = Self::rotate_right(store, right);
     }
-    else {
-        root.right = Some(right)
-    }
-    rotate_left(root)
+    L::make_mut(store, &mut root).right = Some(right);
+    Self::rotate_left(store, root)
 }
 
-fn diff_of_successors_height<D>(root: &Box<Node<D>>) -> i32 {
-    let l = height(&root.left);
-    let r = height(&root.right);
-    (l as i32) - (r as i32)
+/// Apply all necessary rotations on root. 
+fn rotate_if_necessary(store: &mut NodeStore<D, S, L>, root: NodePtr<D, S, L>) -> NodePtr<D, S, L> {
+    let diff  = Self::balance(store, &root);
+    debug_assert!(-2 <= diff && diff <= 2, "Avl broken");
+    match diff{
+        2 => Self::rotate_left_successor(store, root),
+        -2 => Self::rotate_right_successor(store, root),
+        _ => root
+    }
 }
 
+/// Reattaches `child` to the last node of `path`, rebalances it and continues with its parent until
+/// the whole path has been consumed. The nodes on the path had their successor on the stored side
+/// taken out while descending. Returns the new root of the tree.
+fn rebuild_path(store: &mut NodeStore<D, S, L>, mut path: Path<NodePtr<D, S, L>>, mut child: NodePtr<D, S, L>) -> NodePtr<D, S, L> {
+    while let Some((mut parent, side)) = path.pop() {
+        let node = L::make_mut(store, &mut parent);
+        match side {
+            Side::Left => node.left = Some(child),
+            Side::Right => node.right = Some(child)
+        }
+        child = Self::updated_node(store, parent);
+    }
+    child
+}
+
+/// Like `rebuild_path`, but the subtree that replaces the detached successor may be empty. Returns
+/// the new root, or None if the path was empty as well.
+fn reattach(store: &mut NodeStore<D, S, L>, mut path: Path<NodePtr<D, S, L>>, child: OptNodePtr<D, S, L>) -> OptNodePtr<D, S, L> {
+    match child {
+        Some(child) => Some(Self::rebuild_path(store, path, child)),
+        // the successor was taken out while descending, so the parent is already missing it
+        None => path.pop().map(|(parent, _)| {
+            let parent = Self::updated_node(store, parent);
+            Self::rebuild_path(store, path, parent)
+        })
+    }
+}
 
-/// Apply all necessary rotations on root. 
-fn rotate_if_necessary<D>(root: Box<Node<D>>) -> Box<Node<D>> {
-    let diff  = diff_of_successors_height(&root);
-    if -1 <= diff && diff <= 1 {return root}
-    match diff{
-        2 => rotate_left_successor::<D>(root),
-        -2 => rotate_right_successor::<D>(root),
-        _ => unreachable!()
+/// Links the node `new` into the tree root. If its key is already part of the tree, `new` takes the
+/// place of the node stored under it. The resulting tree will be returned (its root may now differ
+/// due to rotations, thus the old root is moved into the function) together with the replaced node,
+/// if any, which is no longer linked into the tree.
+pub fn insert(store: &mut NodeStore<D, S, L>, root: OptNodePtr<D, S, L>, mut new: NodePtr<D, S, L>) -> (NodePtr<D, S, L>, OptNodePtr<D, S, L>){
+    let key = L::get(store, &new).key;
+    let mut path = Path::new();
+    let mut current = root;
+    while let Some(mut ptr) = current.take() {
+        let node = L::make_mut(store, &mut ptr);
+        match node.key.cmp(&key) {
+            Ordering::Equal => {
+                let (left, right) = (node.left.take(), node.right.take());
+                let replacement = L::make_mut(store, &mut new);
+                replacement.left = left;
+                replacement.right = right;
+                Self::update_height(store, &mut new);
+                return (Self::rebuild_path(store, path, new), Some(ptr))
+            },
+            Ordering::Less => { current = node.right.take(); path.push(ptr, Side::Right) },
+            Ordering::Greater => { current = node.left.take(); path.push(ptr, Side::Left) }
+        }
     }
+    (Self::rebuild_path(store, path, new), None)
 }
 
-/// update the cached height of root. To call this function make sure that the cached values of
-/// both children of root ar up to date.
-fn update_height<D>(root: &mut Node<D>){
-    root.height = cmp::max( height(&root.left), height(&root.right) )+1;
-    root.max = cmp::max(subtree_max(&root.left), cmp::max(subtree_max(&root.right), root.key.max));
+//will update_heights and rotate the node if necessary, returns the rotated node
+fn updated_node(store: &mut NodeStore<D, S, L>, mut root: NodePtr<D, S, L>) -> NodePtr<D, S, L> {
+    Self::update_height(store, &mut root);
+    Self::rotate_if_necessary(store, root)
 }
 
-/// recursively insert the (key,data) pair into the given optional succesor and return its new
-/// value
-fn insert_in_successor<D>(key: Range, data: D, successor: Option<Box<Node<D>>>)->Option<Box<Node<D>>> {
-            Some(match successor {
-                Some(succ) => insert(key, data, succ),
-                None =>Box::new(Node::new(key, data))
-            })
+//Finds the minimal value below root and returns a new (optional) tree where the minimal value has been
+//removed and the (optional) minimal node as tuple (new_tree, min);
+fn drop_min(store: &mut NodeStore<D, S, L>, root: NodePtr<D, S, L>) -> (OptNodePtr<D, S, L>, NodePtr<D, S, L>) {
+    let mut path = Path::new();
+    let mut node = root;
+    while let Some(left) = L::make_mut(store, &mut node).left.take() {
+        path.push(node, Side::Left);
+        node = left;
+    }
+    let remaining = L::make_mut(store, &mut node).right.take();
+    (Self::reattach(store, path, remaining), node)
 }
 
-/// Inserts the given data under the key in the tree root. It will replace old data stored
-/// under this key if it was allready used in the tree. The resulting tree will be returned (its
-/// root may now differ due to rotations, thus the old root is moved into the function)
-pub fn insert<D>(key: Range, data: D, mut root: Box<Node<D>>) -> Box<Node<D>>{
-    match root.key.cmp(&key) {
-        Ordering::Equal => { root.data  = data; return root },
-        Ordering::Less =>    root.right = insert_in_successor(key, data, root.right.take()),
-        Ordering::Greater => root.left  = insert_in_successor(key,data, root.left.take())
+//Return a new Interval tree, as the combination of two subtrees with max(l) <= min(r)
+fn combine_two_subtrees(store: &mut NodeStore<D, S, L>, l: NodePtr<D, S, L>, r: NodePtr<D, S, L>) -> NodePtr<D, S, L>{
+    let (remaining_tree, mut new_root) = Self::drop_min(store, r);
+    let node = L::make_mut(store, &mut new_root);
+    node.left = Some(l);
+    node.right = remaining_tree;
+    Self::updated_node(store, new_root)
+}
+
+//Return a new Interval tree, where the root has been unlinked, together with the unlinked root
+fn delete_root(store: &mut NodeStore<D, S, L>, mut root: NodePtr<D, S, L>) -> (OptNodePtr<D, S, L>, NodePtr<D, S, L>) {
+    let node = L::make_mut(store, &mut root);
+    let remaining = match ( node.left.take(), node.right.take() ) {
+        ( None,     None)    => None,
+        ( Some(l),  None)    => Some(l),
+        ( None,     Some(r)) => Some(r),
+        ( Some(l),  Some(r)) => Some(Self::combine_two_subtrees(store, l, r))
+    };
+    (remaining, root)
+}
+
+// will unlink `key` from the tree `root`. Returns the remaining tree (None if it is empty) and the
+// node that was stored under `key`, if any.
+pub fn remove(store: &mut NodeStore<D, S, L>, root: OptNodePtr<D, S, L>, key: &Range) -> (OptNodePtr<D, S, L>, OptNodePtr<D, S, L>){
+    let mut path = Path::new();
+    let mut current = root;
+    while let Some(mut ptr) = current.take() {
+        let node = L::make_mut(store, &mut ptr);
+        match node.key.cmp(key){
+            Ordering::Equal => {
+                let (remaining, removed) = Self::delete_root(store, ptr);
+                return (Self::reattach(store, path, remaining), Some(removed))
+            },
+            Ordering::Less => { current = node.right.take(); path.push(ptr, Side::Right) },
+            Ordering::Greater => { current = node.left.take(); path.push(ptr, Side::Left) }
+        }
     }
-    update_height(&mut *root);
-    return rotate_if_necessary(root)
+    (Self::reattach(store, path, None), None)
+}
+}
+
+/// Inserts the given data under the key in the boxed tree root, see `Node::insert`. Returns the new
+/// root and the replaced data, if any.
+pub fn insert<D, S: Summary<D>>(key: Range, data: D, root: Box<Node<D, S>>) -> (Box<Node<D, S>>, Option<D>){
+    let (root, replaced) = Node::<D, S, Owned>::insert(&mut (), Some(root), Box::new(Node::new(key, data)));
+    (root, replaced.map(|node| node.data))
 }
 
 /// returns a read only reference to the data stored under key in the tree given by root
-pub fn search<'a, D>(key: &Range, root: &'a Box<Node<D>>) -> Option<&'a D>{
-    search_pair(key,root).map(|(_,v)| v )
+pub fn search<'a, D, S, L: Link>(store: &'a NodeStore<D, S, L>, key: &Range, root: &'a Node<D, S, L>) -> Option<&'a D>{
+    search_pair(store, key, root).map(|(_,v)| v )
 }
 
 /// returns a read only reference paie to the data stored under key in the tree given by root
-pub fn search_pair<'a,D>(key: &Range, root: &'a Box<Node<D>>) -> Option<(&'a Range,&'a D)>{
-    match root.key.cmp(key) {
-        Ordering::Equal => Some((&root.key, &root.data)),
-        Ordering::Less => root.right.as_ref().map_or(None, |succ| search_pair(key, succ)),
-        Ordering::Greater => root.left.as_ref().map_or(None, |succ| search_pair(key, succ))
+pub fn search_pair<'a, D, S, L: Link>(store: &'a NodeStore<D, S, L>, key: &Range, root: &'a Node<D, S, L>) -> Option<(&'a Range,&'a D)>{
+    let mut current = Some(root);
+    while let Some(node) = current {
+        let (left, right) = node.successors(store);
+        match node.key.cmp(key) {
+            Ordering::Equal => return Some((&node.key, &node.data)),
+            Ordering::Less => current = right,
+            Ordering::Greater => current = left
+        }
     }
+    None
 }
 
-
 /// returns true iff key is stored in the tree given by root
-#[cfg(test)]
-pub fn contains<D>(key: &Range, root: &Box<Node<D>> ) -> bool  {
-    search(key,root).is_some()
+#[cfg(all(test, feature = "std"))]
+pub fn contains<D, S>(key: &Range, root: &Box<Node<D, S>> ) -> bool  {
+    search(&(), key, root).is_some()
 }
 
 
 ///returns the smallest key and value after the given key.
-#[cfg(test)]
-pub fn min_after<'a,D>(key: &Range, root: &'a Box<Node<D>>) -> Option<(&'a Range,&'a D)> {
+#[cfg(all(test, feature = "std"))]
+pub fn min_after<'a, D, S>(key: &Range, root: &'a Box<Node<D, S>>) -> Option<(&'a Range,&'a D)> {
     match root.key.cmp(key){
         Ordering::Equal =>  root.right.as_ref().map_or(None, |succ| Some(min_pair(succ))),
         Ordering::Less =>   root.right.as_ref().map_or(None, |succ| min_after(key, succ)),
@@ -169,122 +517,223 @@ pub fn min_after<'a,D>(key: &Range, root: &'a Box<Node<D>>) -> Option<(&'a Range
 }
 
 ///returns the minimal key,value pair within this tree
-pub fn min_pair<D>(root: &Box<Node<D>>) -> (&Range,&D) {
-    root.left.as_ref().map_or((&root.key,&root.data), min_pair)
+pub fn min_pair<D, S>(root: &Box<Node<D, S>>) -> (&Range,&D) {
+    let mut node = root;
+    while let Some(ref left) = node.left { node = left }
+    (&node.key, &node.data)
 }
 
 ///returns the maximal key,value pair within this tree
-pub fn max_pair<D>(root: &Box<Node<D>>) -> (&Range,&D) {
-    root.right.as_ref().map_or((&root.key,&root.data), max_pair)
+pub fn max_pair<D, S>(root: &Box<Node<D, S>>) -> (&Range,&D) {
+    let mut node = root;
+    while let Some(ref right) = node.right { node = right }
+    (&node.key, &node.data)
 }
 
 ///returns the minimal value within this tree
-#[cfg(test)]
-pub fn min<D>(root: &Box<Node<D>>) -> &D {
+#[cfg(all(test, feature = "std"))]
+pub fn min<D, S>(root: &Box<Node<D, S>>) -> &D {
     root.left.as_ref().map_or(&root.data, min)
 }
 
 ///returns the minimal value within this tree
-#[cfg(test)]
-pub fn max<D>(root: &Box<Node<D>>) -> &D {
+#[cfg(all(test, feature = "std"))]
+pub fn max<D, S>(root: &Box<Node<D, S>>) -> &D {
     root.right.as_ref().map_or(&root.data, max)
 }
 
-//will update_heights and rotate the node if necessary, returns the rotated node
-fn updated_node<D>(mut root: Box<Node<D>>) -> Box<Node<D>> {
-    update_height(&mut root);
-    rotate_if_necessary(root)
-}
 
-//Performs recursive `drop_and_get_min` if a left  since a successor is available
-fn drop_min_from_left<D>(mut root : Box<Node<D>>, left: Box<Node<D>>) -> (Option<Box<Node<D>>>,Box<Node<D>>) {
-    let (new_left, min) =  drop_min(left);
-    root.left = new_left;
-    (Some(updated_node(root)),min)
+// will delete `key` from the tree `root`. Returns either `Some` tree or if the resilting tree is
+// empty: None.
+//
+//
+#[cfg(all(test, feature = "std"))]
+pub fn delete<D, S: Summary<D>>(key: Range, root: Box<Node<D, S>>) -> Option<Box<Node<D, S>>>{
+    remove(key, root).0
+}
+
+// will remove `key` from the boxed tree `root`, see `Node::remove`.
+pub fn remove<D, S: Summary<D>>(key: Range, root: Box<Node<D, S>>) -> (Option<Box<Node<D, S>>>, Option<D>){
+    let (root, removed) = Node::<D, S, Owned>::remove(&mut (), Some(root), &key);
+    (root, removed.map(|node| node.data))
+}
+
+
+// Returns a new node with the given subtrees, whose heights may differ by at most one.
+pub fn join<D, S: Summary<D>>(left: Option<Box<Node<D, S>>>, key: Range, data: D, right: Option<Box<Node<D, S>>>) -> Box<Node<D, S>> {
+    let mut node = Box::new(Node::new(key, data));
+    node.left = left;
+    node.right = right;
+    Node::<D, S, Owned>::update_height(&mut (), &mut node);
+    node
+}
+
+// Builds a tree from the next `len` entries, which have to be sorted by key without duplicates.
+// Both subtrees of every node get the same number of entries (plus or minus one), so the result is
+// balanced without any rotation.
+pub fn build_balanced<D, S: Summary<D>, I: Iterator<Item=(Range, D)>>(len: usize, entries: &mut I) -> Option<Box<Node<D, S>>> {
+    if len == 0 { return None }
+    let left = build_balanced(len / 2, entries);
+    let (key, data) = entries.next().expect("entries holds at least len elements");
+    let right = build_balanced(len - len / 2 - 1, entries);
+    Some(join(left, key, data, right))
+}
+
+/// The ways in which a node can violate the invariants that the tree operations and the pruning
+/// in `RangePairIter` rely on. Each variant carries the key of the offending node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantError {
+    /// The key is not bigger than every key in the left subtree and smaller than every key in the
+    /// right subtree.
+    Unsorted(Range),
+    /// The cached height does not match the heights of the successors.
+    WrongHeight(Range),
+    /// The heights of the successors differ by more than one.
+    Unbalanced(Range),
+    /// The cached max is not the maximum of all keys in the subtree.
+    WrongMax(Range),
+    /// The cached maximal length is not the maximal length of all keys in the subtree.
+    WrongMaxLen(Range),
+    /// The cached summary is not the summary of all entries in the subtree.
+    WrongSummary(Range),
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InvariantError::Unsorted(k) => write!(f, "node {:?} is not ordered relative to its subtrees", k),
+            InvariantError::WrongHeight(k) => write!(f, "node {:?} caches a wrong height", k),
+            InvariantError::Unbalanced(k) => write!(f, "node {:?} is not balanced", k),
+            InvariantError::WrongMax(k) => write!(f, "node {:?} caches a wrong subtree max", k),
+            InvariantError::WrongMaxLen(k) => write!(f, "node {:?} caches a wrong maximal length", k),
+            InvariantError::WrongSummary(k) => write!(f, "node {:?} caches a wrong summary", k),
+        }
+    }
 }
 
-//Finds the minimal value below root and returns a new (optional) tree where the minimal value has been
-//removed and the (optional) minimal node as tuple (new_tree, min);
-fn drop_min<D>(mut root: Box<Node<D>>) -> (Option<Box<Node<D>>>, Box<Node<D>>) {
-    match root.left.take() {
-        Some(left) => drop_min_from_left(root, left),
-        None => (root.right.take(), root)
+#[cfg(feature = "std")]
+impl error::Error for InvariantError {}
+
+/// checks the invariants of a single node, given the exclusive bounds for its key that result from
+/// its position in the tree.
+fn check_node<D, S, L>(node: &Node<D, S, L>, left: Option<&Node<D, S, L>>, right: Option<&Node<D, S, L>>,
+    lower: Option<&Range>, upper: Option<&Range>) -> Result<(), InvariantError>
+    where S: Summary<D> + PartialEq, L: Link {
+    if lower.is_some_and(|l| node.key <= *l) || upper.is_some_and(|u| node.key >= *u) {
+        return Err(InvariantError::Unsorted(node.key))
+    }
+    let (l, r) = (node_height(left), node_height(right));
+    if node.height != cmp::max(l, r)+1 { return Err(InvariantError::WrongHeight(node.key)) }
+    if (l as i32 - r as i32).abs() > 1 { return Err(InvariantError::Unbalanced(node.key)) }
+    if node.max != cmp::max(subtree_max(left), cmp::max(subtree_max(right), node.key.max)) {
+        return Err(InvariantError::WrongMax(node.key))
+    }
+    if node.max_span != cmp::max(subtree_max_span(left), cmp::max(subtree_max_span(right), node.key.max-node.key.min)) {
+        return Err(InvariantError::WrongMaxLen(node.key))
+    }
+    if node.summary != combined_summary(node, left, right) { return Err(InvariantError::WrongSummary(node.key)) }
+    Ok(())
+}
+
+/// checks every node of the tree given by root, returning the first violation found.
+pub fn check_invariants<D, S: Summary<D> + PartialEq, L: Link>(store: &NodeStore<D, S, L>, root: Option<&Node<D, S, L>>) -> Result<(), InvariantError> {
+    let mut stack = root.into_iter().map(|n| (n, None, None)).collect::<Vec<_>>();
+    while let Some((node, lower, upper)) = stack.pop() {
+        let (left, right) = node.successors(store);
+        check_node(node, left, right, lower, upper)?;
+        if let Some(left) = left { stack.push((left, lower, Some(&node.key))) }
+        if let Some(right) = right { stack.push((right, Some(&node.key), upper)) }
     }
+    Ok(())
 }
 
-//Return a new Interval tree, as the combination of two subtrees with max(l) <= min(r)
-fn combine_two_subtrees<D>(l: Box<Node<D>>, r: Box<Node<D>>) -> Box<Node<D>>{
-    let (remaining_tree, min) = drop_min(r);
-    let mut new_root = min;
-    new_root.left = Some(l);
-    new_root.right = remaining_tree;
-    updated_node(new_root)
+/// A read only view of a node of an `IntervalTree`, e.g. to visualize the structure of the tree.
+#[derive(Debug)]
+pub struct NodeRef<'a, D: 'a, S: 'a = ()> {
+    node: &'a Node<D, S>
 }
 
-//Return a new Interval tree, where the root has been removed
-fn delete_root<D>(mut root: Box<Node<D>>) -> Option<Box<Node<D>>> {
-    match ( root.left.take(), root.right.take() ) {
-        ( None,     None)    => None,
-        ( Some(l),  None)    => Some(l),
-        ( None,     Some(r)) => Some(r),
-        ( Some(l),  Some(r)) => Some(combine_two_subtrees(l,r))
+impl<'a, D: 'a, S: 'a> Clone for NodeRef<'a, D, S> {
+    fn clone(&self) -> NodeRef<'a, D, S> { *self }
+}
+
+impl<'a, D: 'a, S: 'a> Copy for NodeRef<'a, D, S> {}
+
+impl<'a, D: 'a, S: 'a> NodeRef<'a, D, S> {
+    pub(crate) fn new(node: &'a Node<D, S>) -> NodeRef<'a, D, S> {
+        NodeRef { node }
     }
+
+    /// The key stored in this node.
+    pub fn key(&self) -> &'a Range { &self.node.key }
+
+    /// The data stored in this node.
+    pub fn data(&self) -> &'a D { &self.node.data }
+
+    /// The left successor, all keys in its subtree are smaller than `key()`.
+    pub fn left(&self) -> Option<NodeRef<'a, D, S>> { self.node.left.as_ref().map(|n| NodeRef::new(n)) }
+
+    /// The right successor, all keys in its subtree are bigger than `key()`.
+    pub fn right(&self) -> Option<NodeRef<'a, D, S>> { self.node.right.as_ref().map(|n| NodeRef::new(n)) }
+
+    /// The biggest upper bound of all keys in the subtree of this node.
+    pub fn subtree_max(&self) -> u64 { self.node.max }
+
+    /// The height of the subtree of this node, a node without successors has height 1.
+    pub fn height(&self) -> usize { self.node.height as usize }
+
+    /// The summary of all entries in the subtree of this node.
+    pub fn summary(&self) -> &'a S { &self.node.summary }
 }
 
+// Drops the tree `root` without recursing, so that even degenerate trees can't overflow the stack.
+pub fn drop_iteratively<D, S>(root: Option<Box<Node<D, S>>>) {
+    let mut stack = root.into_iter().collect::<Vec<_>>();
+    while let Some(mut node) = stack.pop() {
+        stack.extend(node.left.take());
+        stack.extend(node.right.take());
+    }
+}
 
-// will delete `key` from the tree `root`. Returns either `Some` tree or if the resilting tree is
-// empty: None.
-//
-//
-pub fn delete<D>(key: Range, mut root: Box<Node<D>>) -> Option<Box<Node<D>>>{
-    match root.key.cmp(&key){
-        Ordering::Equal =>  return delete_root(root),
-        Ordering::Less => {
-            if let Some(succ) = root.right.take() {
-                root.right = delete(key, succ);
-                return Some(updated_node(root))
-            }
-        },
-        Ordering::Greater => {
-            if let Some(succ) = root.left.take() {
-                root.left =  delete(key, succ);
-                return Some(updated_node(root))
-            }
+// Moves the entries of the tree `root` into a vector sorted by key, without recursing.
+pub fn into_sorted<D, S>(mut root: Option<Box<Node<D, S>>>) -> Vec<(Range, D)> {
+    let mut entries = Vec::new();
+    let mut stack = Vec::new();
+    loop {
+        while let Some(mut node) = root {
+            root = node.left.take();
+            stack.push(node);
+        }
+        match stack.pop() {
+            Some(mut node) => {
+                root = node.right.take();
+                let Node { key, data, .. } = *node;
+                entries.push((key, data));
+            },
+            None => return entries,
         }
     }
-    return Some(root);
 }
 
-
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 fn simple_tree(size: i32) -> Box<Node<i32>> {
-    let mut t = Box::new(Node::<i32>{key: Range::new(1,1), data: 1337, height: 0, max: 1, left:None, right: None});
+    let mut t = Box::new(Node::<i32>{key: Range::new(1,1), data: 1337, height: 0, max: 1, max_span: 0, summary: (), left:None, right: None});
     for x in 2..size+1 {
-        t = insert(Range::new(x as u64, x as u64 ),1337+x-1,t)
+        t = insert(Range::new(x as u64, x as u64 ),1337+x-1,t).0
     }
     t
 }
-#[cfg(test)]
-fn is_sorted_left<D>(node: &Box<Node<D>>) -> bool {
-    node.left.as_ref().map_or(true, |succ| succ.key < node.key)
-}
-#[cfg(test)]
-fn is_sorted_right<D>(node: &Box<Node<D>>) -> bool {
-    node.right.as_ref().map_or(true, |succ| succ.key > node.key)
-}
-#[cfg(test)]
-fn is_interval_node<D>(node: &Box<Node<D>>) -> bool {
-    let sorted = is_sorted_left(node) && is_sorted_right(node);
-    let balanced = node.height == cmp::max(height(&node.left),height(&node.right))+1;
-    let proper_max = node.max == cmp::max(subtree_max(&node.left), cmp::max(subtree_max(&node.right), node.key.max));
-    return sorted && balanced && proper_max;
+#[cfg(all(test, feature = "std"))]
+fn is_interval_node<D, S: Summary<D> + PartialEq>(node: &Box<Node<D, S>>) -> bool {
+    check_invariants(&(), Some(&**node)).is_ok()
 }
 
-#[cfg(test)]
-pub fn is_interval_tree<D>(root: &Option<Box<Node<D>>>) -> bool {
-    (*root).as_ref().map_or(true, is_interval_node)
+#[cfg(all(test, feature = "std"))]
+pub fn is_interval_tree<D, S: Summary<D> + PartialEq>(root: &Option<Box<Node<D, S>>>) -> bool {
+    check_invariants(&(), root.as_deref()).is_ok()
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn range_impl() {
     assert_eq!((&Range::new(3,10)).get_intersection(&Range::new(5,17)), Range::new(5,10) );
@@ -293,97 +742,113 @@ fn range_impl() {
     assert_eq!(Range::new(2,3).get_union(&Range::new(5,6)), Range::new(2,6));
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn simple_tree_operations() {
-    let mut t = Box::new(Node::<i32>{key: Range::new(3,3), data: 4, max:3, height: 2,
-        left: Some(Box::new(Node::<i32>{key: Range::new(2,2), data: 5, height:1, max: 2, left: None, right: None})), 
+    let mut t = Box::new(Node::<i32>{key: Range::new(3,3), data: 4, max:3, max_span: 0, height: 2, summary: (),
+        left: Some(Box::new(Node::<i32>{key: Range::new(2,2), data: 5, height:1, max: 2, max_span: 0, summary: (), left: None, right: None})), 
         right: None});
     assert!(is_interval_node(&t));
-    assert!( contains::<i32>(&Range::new(3,3),&t) );
-    assert!( contains::<i32>(&Range::new(2,2),&t) );
-    assert!( !contains::<i32>(&Range::new(6,6),&t) );
-    assert!( !contains::<i32>(&Range::new(4,4),&t) );
-    t = insert::<i32>(Range::new(4,4),7, t);
-    t = insert::<i32>(Range::new(5,5),7, t);
-    t = insert::<i32>(Range::new(6,6),8, t);
-    assert!(  contains::<i32>(&Range::new(4,4),&t) );
-    assert!(  contains::<i32>(&Range::new(6,6),&t) );
-    assert!( !contains::<i32>(&Range::new(7,7),&t) );
-}
-
+    assert!( contains(&Range::new(3,3),&t) );
+    assert!( contains(&Range::new(2,2),&t) );
+    assert!( !contains(&Range::new(6,6),&t) );
+    assert!( !contains(&Range::new(4,4),&t) );
+    t = insert(Range::new(4,4),7, t).0;
+    t = insert(Range::new(5,5),7, t).0;
+    t = insert(Range::new(6,6),8, t).0;
+    assert!(  contains(&Range::new(4,4),&t) );
+    assert!(  contains(&Range::new(6,6),&t) );
+    assert!( !contains(&Range::new(7,7),&t) );
+}
+
+#[cfg(feature = "std")]
 #[test]
 fn rotations_on_tree(){ 
-    let mut t = Box::new(Node::<i32>{key: Range::new(1,1), data: 1337, height: 1, max: 1, left: None, right: None});
+    let mut t = Box::new(Node::<i32>{key: Range::new(1,1), data: 1337, height: 1, max: 1, max_span: 0, summary: (), left: None, right: None});
     for i in 2..255 {
-        t = insert::<i32>(Range::new(i,i),1337, t);
+        t = insert(Range::new(i,i),1337, t).0;
         assert!(is_interval_node(&t));
     }
     //check that the tree is indeed balanced
     assert!(height(&Some(t)) <= 8);
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_drop_min(){
     let mut t = simple_tree(3);
-    let (maybe_tree,min) = drop_min(t);
+    let (maybe_tree,min) = Node::<i32>::drop_min(&mut (), t);
     t = maybe_tree.expect("failure to get tree for first min delete");
     assert!(is_interval_node(&t));
     assert!( min.key == Range::new(1,1));
-    assert!(!contains::<i32>(&Range::new(1,1),&t));
-    assert!( contains::<i32>(&Range::new(2,2),&t));
-    assert!( contains::<i32>(&Range::new(3,3),&t));
+    assert!(!contains(&Range::new(1,1),&t));
+    assert!( contains(&Range::new(2,2),&t));
+    assert!( contains(&Range::new(3,3),&t));
 
-    let (maybe_tree,min) = drop_min(t);
+    let (maybe_tree,min) = Node::<i32>::drop_min(&mut (), t);
     t = maybe_tree.expect("failure to get tree for second min delete");
     assert!(is_interval_node(&t));
     assert!( min.key == Range::new(2,2));
-    assert!(!contains::<i32>(&Range::new(1,1),&t));
-    assert!(!contains::<i32>(&Range::new(2,2),&t));
-    assert!( contains::<i32>(&Range::new(3,3),&t));
+    assert!(!contains(&Range::new(1,1),&t));
+    assert!(!contains(&Range::new(2,2),&t));
+    assert!( contains(&Range::new(3,3),&t));
 
-    let (maybe_tree,min) = drop_min(t);
+    let (maybe_tree,min) = Node::<i32>::drop_min(&mut (), t);
     assert!( maybe_tree.is_none() );
     assert!( min.key == Range::new(3,3));
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn test_build_balanced(){
+    for len in 0..70 {
+        let t = build_balanced::<i32, (), _>(len, &mut (0..len as u64).map(|i| (Range::new(i, i+2), i as i32)));
+        assert!(is_interval_tree(&t));
+        assert_eq!(height(&t) as usize, (usize::BITS - len.leading_zeros()) as usize);
+    }
+}
+
+#[cfg(feature = "std")]
 #[test]
 fn test_drop_root(){
     let mut t = simple_tree(3);
-    let maybe_tree = delete_root(t);
+    let (maybe_tree, _) = Node::<i32>::delete_root(&mut (), t);
     t = maybe_tree.expect("failure to get tree for first root drop");
     assert!(is_interval_node(&t));
     assert!( t.height == 2);
-    assert!( contains::<i32>(&Range::new(1,1),&t));
-    assert!(!contains::<i32>(&Range::new(2,2),&t));
-    assert!( contains::<i32>(&Range::new(3,3),&t));
+    assert!( contains(&Range::new(1,1),&t));
+    assert!(!contains(&Range::new(2,2),&t));
+    assert!( contains(&Range::new(3,3),&t));
 
-    let maybe_tree = delete_root(t);
+    let (maybe_tree, _) = Node::<i32>::delete_root(&mut (), t);
     t = maybe_tree.expect("failure to get tree for second root drop");
     assert!(is_interval_node(&t));
-    assert!( contains::<i32>(&Range::new(1,1),&t));
-    assert!(!contains::<i32>(&Range::new(2,2),&t));
-    assert!(!contains::<i32>(&Range::new(3,3),&t));
+    assert!( contains(&Range::new(1,1),&t));
+    assert!(!contains(&Range::new(2,2),&t));
+    assert!(!contains(&Range::new(3,3),&t));
 
-    let maybe_tree = delete_root(t);
+    let (maybe_tree, _) = Node::<i32>::delete_root(&mut (), t);
     assert!( maybe_tree.is_none() );
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_delete(){
     let mut t = simple_tree(10);
     for i in 1..10 {
-        assert!(contains::<i32>(&Range::new(i,i),&t));
+        assert!(contains(&Range::new(i,i),&t));
         let maybe_tree = delete(Range::new(i,i),t);
         t = maybe_tree.expect("failure to get tree for delete");
-        assert!(!contains::<i32>(&Range::new(i,i),&t));
+        assert!(!contains(&Range::new(i,i),&t));
         assert!(is_interval_node(&t));
     }
-    assert!(contains::<i32>(&Range::new(10,10),&t));
+    assert!(contains(&Range::new(10,10),&t));
     let maybe_tree = delete(Range::new(10,10),t);
     assert!(maybe_tree.is_none());
 }
 
-#[test] 
+#[cfg(feature = "std")]
+#[test]
 fn test_min_max() {
     let t = simple_tree(50);
     assert_eq!(min(&t),&1337);
@@ -392,6 +857,7 @@ fn test_min_max() {
     assert_eq!(min_pair(&t).0,&Range::new(1,1));
 }
 
+#[cfg(feature = "std")]
 #[test]
 fn test_min_after(){
     let t = simple_tree(50);
@@ -0,0 +1,150 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::tree::IntervalTree;
+
+/// Receives a notification for every change of an `IntervalTree` it is registered on, e.g. to keep
+/// a UI or a secondary index in sync with the tree. All methods do nothing by default, so an
+/// observer only implements the changes it cares about. The notifications are sent after the tree
+/// was changed, by `insert`, `try_insert`, `insert_bounds`, `delete` and `remove`.
+///
+/// Observers only borrow the data, also on removal: `remove` returns the removed data to its caller
+/// and several observers can be registered, so none of them can take it. Observers that need to
+/// keep the data can clone it.
+///
+/// The observers of a tree created by `new` have to be `Send + Sync`. A tree that names
+/// `dyn TreeObserver<D>` as its observer type accepts any observer, e.g. one that updates a single
+/// threaded UI model, but can't be shared between threads.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+/// use memrange::Range;
+/// use theban_interval_tree::{IntervalTree, TreeObserver};
+///
+/// struct Model(Rc<RefCell<Vec<Range>>>);
+///
+/// impl TreeObserver<i32> for Model {
+///     fn on_insert(&mut self, key: Range, _data: &i32) { self.0.borrow_mut().push(key) }
+/// }
+///
+/// let rows = Rc::new(RefCell::new(Vec::new()));
+/// let mut t = IntervalTree::<i32, (), dyn TreeObserver<i32>>::default();
+/// t.add_observer(Box::new(Model(rows.clone())));
+/// t.insert(Range::new(2,3), 25);
+/// assert_eq!(*rows.borrow(), vec![Range::new(2,3)]);
+/// ```
+pub trait TreeObserver<D> {
+    /// called when a new key was inserted
+    fn on_insert(&mut self, _key: Range, _data: &D) {}
+
+    /// called when the data of an existing key was overwritten
+    fn on_replace(&mut self, _key: Range, _old: &D, _new: &D) {}
+
+    /// called when a key was removed, before its data is dropped or returned by `remove`
+    fn on_remove(&mut self, _key: Range, _data: &D) {}
+}
+
+/// The observers registered on a tree, notified in the order they were added.
+pub(crate) type Observers<O> = Vec<Box<O>>;
+
+impl<D, S, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will register an observer that is notified about every later change of the
+/// tree. Several observers can be registered, they are notified in the order they were added.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use std::sync::{Arc, Mutex};
+/// use memrange::Range;
+/// use theban_interval_tree::{IntervalTree, TreeObserver};
+///
+/// struct Log(Arc<Mutex<Vec<String>>>);
+///
+/// impl TreeObserver<i32> for Log {
+///     fn on_insert(&mut self, key: Range, data: &i32) {
+///         self.0.lock().unwrap().push(format!("insert {}..={} {}", key.min, key.max, data))
+///     }
+///     fn on_remove(&mut self, key: Range, data: &i32) {
+///         self.0.lock().unwrap().push(format!("remove {}..={} {}", key.min, key.max, data))
+///     }
+/// }
+///
+/// let log = Arc::new(Mutex::new(Vec::new()));
+/// let mut t = IntervalTree::<i32>::new();
+/// t.add_observer(Box::new(Log(log.clone())));
+/// t.insert(Range::new(2,3), 25);
+/// t.delete(Range::new(2,3));
+/// t.delete(Range::new(2,3));
+/// assert_eq!(*log.lock().unwrap(), vec!["insert 2..=3 25", "remove 2..=3 25"]);
+/// ```
+    pub fn add_observer(&mut self, observer: Box<O>) {
+        self.observers.push(observer)
+    }
+
+/// This function will unregister all observers and return them.
+    pub fn take_observers(&mut self) -> Vec<Box<O>> {
+        core::mem::take(&mut self.observers)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use super::TreeObserver;
+    use error::OnDuplicate;
+
+    /// mirrors the tree into a map and counts the replacements
+    struct Mirror {
+        map: Arc<Mutex<BTreeMap<memrange::Range, u64>>>,
+        replaced: Arc<Mutex<u64>>,
+    }
+
+    impl TreeObserver<u64> for Mirror {
+        fn on_insert(&mut self, key: memrange::Range, data: &u64) {
+            assert!(self.map.lock().unwrap().insert(key, *data).is_none());
+        }
+
+        fn on_replace(&mut self, key: memrange::Range, old: &u64, new: &u64) {
+            assert_eq!(self.map.lock().unwrap().insert(key, *new), Some(*old));
+            *self.replaced.lock().unwrap() += 1;
+        }
+
+        fn on_remove(&mut self, key: memrange::Range, data: &u64) {
+            assert_eq!(self.map.lock().unwrap().remove(&key), Some(*data));
+        }
+    }
+
+    #[test]
+    fn test_observer_mirror(){
+        let map = Arc::new(Mutex::new(BTreeMap::new()));
+        let replaced = Arc::new(Mutex::new(0));
+        let mut t = ::IntervalTree::<u64>::new();
+        t.add_observer(Box::new(Mirror { map: map.clone(), replaced: replaced.clone() }));
+        let mut replacements = 0;
+        for i in 0..5000 {
+            let offset = rand::random::<u64>() % 100;
+            let key = memrange::Range::new(offset, offset + rand::random::<u64>() % 20);
+            let present = t.contains(key);
+            match rand::random::<u8>() % 5 {
+                0 => t.insert(key, i),
+                1 => { let _ = t.try_insert(key, i, OnDuplicate::Reject); },
+                2 => { t.insert_bounds(key.min..=key.max, i).unwrap(); },
+                3 => t.delete(key),
+                _ => assert_eq!(t.remove(key).is_some(), present),
+            }
+            if present && t.get(key) == Some(&i) { replacements += 1 }
+            let mirrored = map.lock().unwrap().iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+            assert_eq!(t.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(), mirrored);
+        }
+        assert_eq!(*replaced.lock().unwrap(), replacements);
+        assert_eq!(t.take_observers().len(), 1);
+        t.insert(memrange::Range::new(1000, 1000), 0);
+        assert!(!map.lock().unwrap().contains_key(&memrange::Range::new(1000, 1000)));
+    }
+}
@@ -0,0 +1,198 @@
+extern crate rayon;
+
+use core::ops::RangeBounds;
+use ::range::Range;
+use self::rayon::prelude::*;
+use ::node::{Node, build_balanced, join};
+use ::tree::IntervalTree;
+use ::iterators::RangePairIter;
+use ::error::Error;
+use ::summary::Summary;
+use ::bounds::closed_range;
+
+/// subtrees of at most this height are walked sequentially instead of being split further
+const SEQUENTIAL_HEIGHT: u32 = 8;
+
+/// slices of at most this many entries are built sequentially by `par_from_sorted`
+const SEQUENTIAL_LEN: usize = 4096;
+
+/// a part of a query result: either a whole subtree or the entry of a single node
+enum Part<'a, D: 'a, S: 'a> {
+    Subtree(&'a Node<D, S>),
+    Entry(&'a Node<D, S>),
+}
+
+/// consecutive parts of a query result, the unit of work that rayon splits and distributes
+struct Piece<'a, D: 'a, S: 'a> {
+    range: Range,
+    parts: Vec<Part<'a, D, S>>,
+}
+
+/// splits piece into two halves of its parts. A piece that is a single big subtree is first
+/// replaced by the relevant parts of its root: the left subtree, the root entry and the right
+/// subtree.
+fn split_piece<D, S>(mut piece: Piece<'_, D, S>) -> (Piece<'_, D, S>, Option<Piece<'_, D, S>>) {
+    while let [Part::Subtree(node)] = piece.parts[..] {
+        if node.height <= SEQUENTIAL_HEIGHT { break }
+        piece.parts.clear();
+        if let Some(ref left) = node.left {
+            if node.left_subtree_relevant(&piece.range) { piece.parts.push(Part::Subtree(left)) }
+        }
+        if node.key.intersect(&piece.range) { piece.parts.push(Part::Entry(node)) }
+        if let Some(ref right) = node.right {
+            if node.right_subtree_relevant(&piece.range) { piece.parts.push(Part::Subtree(right)) }
+        }
+    }
+    if piece.parts.len() < 2 { return (piece, None) }
+    let rest = piece.parts.split_off(piece.parts.len() / 2);
+    let range = piece.range;
+    (piece, Some(Piece { range, parts: rest }))
+}
+
+/// sequential iterator over the results of a single part
+enum PartIter<'a, D: 'a, S: 'a> {
+    Subtree(RangePairIter<'a, D, S>),
+    Entry(Option<(Range, &'a D)>),
+}
+
+impl<'a, D: 'a, S: 'a> Iterator for PartIter<'a, D, S> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        match *self {
+            PartIter::Subtree(ref mut iter) => iter.next(),
+            PartIter::Entry(ref mut entry) => entry.take(),
+        }
+    }
+}
+
+fn part_iter<'a, D, S>(part: Part<'a, D, S>, range: Range) -> PartIter<'a, D, S> {
+    match part {
+        Part::Subtree(node) => PartIter::Subtree(RangePairIter::from_root(Some(node), range.min, range.max)),
+        Part::Entry(node) => PartIter::Entry(Some((node.key, &node.data))),
+    }
+}
+
+/// builds the tree from keys and the data in slots, taking it out. The shape is the same as the one
+/// of `build_balanced`, but both halves of big slices are built in parallel.
+fn build_slots<D: Send, S: Summary<D> + Send>(keys: &[Range], slots: &mut [Option<D>]) -> Option<Box<Node<D, S>>> {
+    if slots.len() <= SEQUENTIAL_LEN {
+        let mut entries = keys.iter().cloned().zip(slots.iter_mut().map(|slot| slot.take().expect("every slot is taken once")));
+        return build_balanced(keys.len(), &mut entries)
+    }
+    let mid = slots.len() / 2;
+    let (left_slots, rest) = slots.split_at_mut(mid);
+    let (center, right_slots) = rest.split_first_mut().expect("right half is not empty");
+    let data = center.take().expect("every slot is taken once");
+    let (left, right) = rayon::join(|| build_slots(&keys[..mid], left_slots), || build_slots(&keys[mid + 1..], right_slots));
+    Some(join(left, keys[mid], data, right))
+}
+
+impl<D: Sync, S: Sync, O: ?Sized> IntervalTree<D, S, O> {
+
+/// This function will return a parallel iterator for all (key,value) pairs intersecting the given
+/// bounds. The work is split along the left and right subtrees of the nodes, and collecting it
+/// keeps the key order of `range_bounds`. Only available with the `rayon` feature.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate rayon;
+/// extern crate theban_interval_tree;
+/// use rayon::prelude::*;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<u64>::new();
+/// for i in 0..1000 { t.insert(memrange::Range::new(i,i+10),i) }
+/// assert_eq!(t.par_range(100..=200).map(|(_,v)| *v).sum::<u64>(), (90..201).sum());
+/// ```
+    pub fn par_range<R: RangeBounds<u64>>(&self, bounds: R) -> impl ParallelIterator<Item = (Range, &D)> + '_ {
+        self.par_range_closed(closed_range(&bounds))
+    }
+
+    /// the parallel iterator of `par_range` for the closed form of its bounds, None for empty ones
+    fn par_range_closed(&self, range: Option<Range>) -> impl ParallelIterator<Item = (Range, &D)> + '_ {
+        let parts = match range {
+            Some(_) => self.root.as_ref().map(|root| Part::Subtree(&**root)).into_iter().collect(),
+            None => Vec::new()
+        };
+        let range = range.unwrap_or(Range::new(0, 0));
+        rayon::iter::split(Piece { range, parts }, split_piece)
+            .flat_map_iter(|piece| {
+                let range = piece.range;
+                piece.parts.into_iter().flat_map(move |part| part_iter(part, range))
+            })
+    }
+
+/// This function will return a parallel iterator for all (key,value) pairs in the tree. Only
+/// available with the `rayon` feature.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (Range, &D)> + '_ {
+        self.par_range(..)
+    }
+}
+
+impl<D: Send, S: Summary<D> + Send, O: ?Sized> IntervalTree<D, S, O> {
+
+/// This function will build a tree from entries that are sorted by key without duplicates like
+/// `from_sorted`, but checks the order and builds the subtrees in parallel. Only available with
+/// the `rayon` feature.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use theban_interval_tree::IntervalTree;
+///
+/// let entries = (0..10000).map(|i| (memrange::Range::new(i,i+5), i)).collect::<Vec<_>>();
+/// let t = IntervalTree::<u64>::par_from_sorted(entries).unwrap();
+/// assert_eq!(t.range(100,100).count(), 6);
+/// ```
+    pub fn par_from_sorted(entries: Vec<(Range, D)>) -> Result<IntervalTree<D, S, O>, Error> {
+        let (keys, mut slots): (Vec<Range>, Vec<Option<D>>) = entries.into_par_iter().map(|(key, data)| (key, Some(data))).unzip();
+        if let Some(window) = keys.par_windows(2).find_first(|w| w[0] >= w[1]) {
+            return Err(Error::Unsorted(window[1]))
+        }
+        Ok(IntervalTree::from_root(build_slots(&keys, &mut slots), keys.len()))
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use super::rayon::prelude::*;
+    use error::Error;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%100_000;
+        let len = rand::random::<u64>()%1000;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_par_range(){
+        let mut t = ::IntervalTree::<u64>::new();
+        for i in 0..50_000 {
+            t.insert(random_range(), i);
+        }
+        assert_eq!(t.par_iter().collect::<Vec<_>>(), t.iter().collect::<Vec<_>>());
+        for _ in 0..50 {
+            let q = random_range();
+            assert_eq!(t.par_range(q.min..=q.max).collect::<Vec<_>>(), t.range(q.min, q.max).collect::<Vec<_>>());
+        }
+        let empty = ::IntervalTree::<u64>::new();
+        assert_eq!(empty.par_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_par_from_sorted(){
+        for &len in &[0, 1, 4095, 4096, 4097, 100_000] {
+            let entries = (0..len).map(|i| (memrange::Range::new(i, i + i % 17), i)).collect::<Vec<_>>();
+            let t = ::IntervalTree::<u64>::par_from_sorted(entries.clone()).expect("sorted entries");
+            assert!(t.check_invariants().is_ok());
+            let sequential = ::IntervalTree::<u64>::from_sorted(entries.clone()).expect("sorted entries");
+            assert_eq!(t.height(), sequential.height());
+            assert_eq!(t.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(), entries);
+        }
+        let mut entries = (0..10_000).map(|i| (memrange::Range::new(i, i), i)).collect::<Vec<_>>();
+        entries[5000].0 = memrange::Range::new(4999, 4999);
+        assert_eq!(::IntervalTree::<u64>::par_from_sorted(entries).err(), Some(Error::Unsorted(memrange::Range::new(4999, 4999))));
+    }
+}
@@ -0,0 +1,100 @@
+//! The key type of the trees. With the `std` feature this is `memrange::Range`, without it (e.g.
+//! in `no_std` builds, where memrange is not available) a compatible type with the same fields and
+//! the methods used by this crate.
+
+#[cfg(feature = "std")]
+pub use memrange::Range;
+
+#[cfg(not(feature = "std"))]
+pub use self::closed::Range;
+
+#[cfg(not(feature = "std"))]
+mod closed {
+    use core::cmp;
+
+    /// A closed range of u64, `Range{min: 2, max: 3}` contains the points 2 and 3. Ranges are
+    /// ordered by `min` first and `max` second, like `memrange::Range`.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Range {
+        pub min: u64,
+        pub max: u64,
+    }
+
+    impl Range {
+        /// returns the range from min to max, both inclusive. Panics if min > max.
+        pub fn new(min: u64, max: u64) -> Range {
+            assert!(min <= max);
+            Range { min, max }
+        }
+
+        /// returns true if both ranges share at least one point
+        pub fn intersect(&self, other: &Range) -> bool {
+            cmp::max(self.min, other.min) <= cmp::min(self.max, other.max)
+        }
+
+        /// returns the points that are in both ranges, which have to intersect
+        pub fn get_intersection(&self, other: &Range) -> Range {
+            Range::new(cmp::max(self.min, other.min), cmp::min(self.max, other.max))
+        }
+
+        /// returns the smallest range containing both ranges
+        pub fn get_union(&self, other: &Range) -> Range {
+            Range::new(cmp::min(self.min, other.min), cmp::max(self.max, other.max))
+        }
+    }
+}
+
+// These tests only use core, so they also run without the `std` feature and check that the
+// replacement type behaves like `memrange::Range`.
+#[cfg(test)]
+mod tests {
+    use super::Range;
+
+    const MAX: u64 = 0xffff_ffff_ffff_ffff;
+
+    #[test]
+    fn test_range_touching_bounds(){
+        let (a, b) = (Range::new(0, 5), Range::new(5, 9));
+        assert!(a.intersect(&b) && b.intersect(&a));
+        assert_eq!(a.get_intersection(&b), Range::new(5, 5));
+        assert_eq!(a.get_union(&b), Range::new(0, 9));
+        let c = Range::new(6, 9);
+        assert!(!a.intersect(&c) && !c.intersect(&a));
+        assert!(Range::new(3, 3).intersect(&Range::new(3, 3)));
+        assert!(!Range::new(3, 3).intersect(&Range::new(4, 4)));
+    }
+
+    #[test]
+    fn test_range_max(){
+        let all = Range::new(0, MAX);
+        let top = Range::new(MAX, MAX);
+        assert!(all.intersect(&top) && top.intersect(&all));
+        assert_eq!(all.get_intersection(&top), top);
+        assert_eq!(top.get_union(&Range::new(0, 0)), all);
+        assert!(!top.intersect(&Range::new(0, MAX - 1)));
+        let below = Range::new(MAX - 1, MAX - 1);
+        assert_eq!(Range::new(MAX - 1, MAX).get_intersection(&Range::new(5, MAX - 1)), below);
+    }
+
+    #[test]
+    fn test_range_disjoint_union(){
+        let (a, b) = (Range::new(2, 3), Range::new(10, 12));
+        assert!(!a.intersect(&b));
+        assert_eq!(a.get_union(&b), Range::new(2, 12));
+        assert_eq!(b.get_union(&a), Range::new(2, 12));
+        assert_eq!(a.get_union(&a), a);
+    }
+
+    #[test]
+    fn test_range_order(){
+        assert!(Range::new(1, 9) < Range::new(2, 3));
+        assert!(Range::new(2, 3) < Range::new(2, 4));
+        assert_eq!(Range { min: 4, max: 7 }, Range::new(4, 7));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_range_inverted(){
+        Range::new(5, 4);
+    }
+}
@@ -0,0 +1,279 @@
+extern crate arc_swap;
+
+use std::sync::{Arc, Mutex};
+use std::ops::RangeBounds;
+use ::range::Range;
+use ::bounds::closed_range;
+use ::node::{Node, Shared, InvariantError, search, check_invariants};
+use ::iterators::NodeRangeIter;
+use self::arc_swap::ArcSwap;
+
+/// Iterator over the (key,value) pairs of an `RcuSnapshot` that intersect a range.
+pub struct RcuRangeIter<'a, D: 'a> {
+    inner: NodeRangeIter<'a, D, (), Shared>,
+}
+
+impl<'a, D: 'a> Iterator for RcuRangeIter<'a, D> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        self.inner.next()
+    }
+}
+
+/// An immutable version of an `RcuIntervalTree`. Cloning it is cheap, and it stays valid and
+/// unchanged while the tree it was loaded from is modified. Its nodes are the nodes of
+/// `IntervalTree`, linked by `Arc`s instead of `Box`es, and they are freed once the last snapshot
+/// or tree version referring to them is dropped.
+#[derive(Debug)]
+pub struct RcuSnapshot<D> {
+    root: Option<Arc<Node<D, (), Shared>>>,
+    len: usize,
+}
+
+impl<D> Clone for RcuSnapshot<D> {
+    fn clone(&self) -> RcuSnapshot<D> {
+        RcuSnapshot { root: self.root.clone(), len: self.len }
+    }
+}
+
+impl<D> RcuSnapshot<D> {
+
+/// This function will return the data stored under the key, if any.
+    pub fn get(&self, key: Range) -> Option<&D> {
+        self.root.as_deref().and_then(|root| search(&(), &key, root))
+    }
+
+/// This function will return true if the key is stored in the snapshot.
+    pub fn contains(&self, key: Range) -> bool {
+        self.get(key).is_some()
+    }
+
+/// This function will return the number of entries in the snapshot.
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return true if the snapshot has no entries.
+    pub fn is_empty(&self) -> bool { self.len == 0 }
+
+/// This function will return an iterator over all (key,value) pairs intersecting the two bounds in
+/// key order.
+    pub fn range(&self, min: u64, max: u64) -> RcuRangeIter<'_, D> {
+        RcuRangeIter { inner: NodeRangeIter::from_root(&(), self.root.as_deref(), min, max) }
+    }
+
+/// This function will return an iterator over all (key,value) pairs intersecting the given bounds
+/// in key order. The bounds may be inclusive, exclusive or unbounded on either side, empty bounds
+/// yield nothing.
+    pub fn range_bounds<R: RangeBounds<u64>>(&self, bounds: R) -> RcuRangeIter<'_, D> {
+        match closed_range(&bounds) {
+            Some(range) => self.range(range.min, range.max),
+            None => RcuRangeIter { inner: NodeRangeIter::from_root(&(), None, 0, 0) },
+        }
+    }
+
+/// This function will return an iterator over all (key,value) pairs containing the point.
+    pub fn stab(&self, point: u64) -> RcuRangeIter<'_, D> {
+        self.range(point, point)
+    }
+
+/// This function will return an iterator over all (key,value) pairs in key order.
+    pub fn iter(&self) -> RcuRangeIter<'_, D> {
+        self.range(0, 0xffff_ffff_ffff_ffff)
+    }
+
+/// This function will check that every node of the snapshot is ordered, balanced and caches the
+/// correct height and subtree max, returning the first violation found.
+    pub fn check_invariants(&self) -> Result<(), InvariantError> {
+        check_invariants(&(), self.root.as_deref())
+    }
+}
+
+/// An interval tree for read mostly workloads, whose readers never block. Readers `load` the
+/// current version as an `RcuSnapshot` without taking any lock, and query it for as long as they
+/// like. Writers build a new version by copying the path to the nodes they change and atomically
+/// publish it, concurrent writers are serialized by a mutex so no update is lost. Old versions are
+/// freed as soon as the last snapshot referring to them is dropped. Copying a node clones its data,
+/// so big data should be stored behind an `Arc`.
+#[derive(Debug)]
+pub struct RcuIntervalTree<D> {
+    current: ArcSwap<RcuSnapshot<D>>,
+    writer: Mutex<()>,
+}
+
+impl<D> RcuIntervalTree<D> {
+
+/// This function will construct a new empty RcuIntervalTree.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let t = theban_interval_tree::RcuIntervalTree::<i32>::new();
+/// t.insert(Range::new(2,8), 1);
+/// let before = t.load();
+/// t.insert(Range::new(5,6), 2);
+/// assert_eq!(before.stab(5).count(), 1);
+/// assert_eq!(t.load().stab(5).count(), 2);
+/// ```
+    pub fn new() -> RcuIntervalTree<D> {
+        RcuIntervalTree { current: ArcSwap::from_pointee(RcuSnapshot { root: None, len: 0 }), writer: Mutex::new(()) }
+    }
+
+/// This function will return the current version of the tree. It never blocks, and later writes
+/// don't affect the returned snapshot.
+    pub fn load(&self) -> Arc<RcuSnapshot<D>> {
+        self.current.load_full()
+    }
+
+    /// builds a new version from the current one and publishes it, while holding the writer lock
+    fn update<F, R>(&self, f: F) -> R where F: FnOnce(&RcuSnapshot<D>) -> (Option<RcuSnapshot<D>>, R) {
+        let _guard = self.writer.lock().expect("writer lock poisoned");
+        let (next, result) = f(&self.current.load());
+        if let Some(next) = next { self.current.store(Arc::new(next)) }
+        result
+    }
+
+}
+
+impl<D: Clone> RcuIntervalTree<D> {
+
+/// This function will insert the key,value pair and publish the new version. It returns the data
+/// that was stored under the key before, if any.
+    pub fn insert(&self, key: Range, data: D) -> Option<D> {
+        self.update(|current| {
+            let (root, replaced) = Node::<D, (), Shared>::insert(&mut (), current.root.clone(), Arc::new(Node::new(key, data)));
+            let old = replaced.map(into_data);
+            let len = if old.is_some() { current.len } else { current.len + 1 };
+            (Some(RcuSnapshot { root: Some(root), len }), old)
+        })
+    }
+
+/// This function will remove the key and publish the new version. It returns the data that was
+/// stored under the key, if any. Snapshots loaded earlier keep seeing it.
+    pub fn remove(&self, key: Range) -> Option<D> {
+        self.update(|current| match current.root.clone() {
+            Some(root) if current.contains(key) => {
+                let (root, removed) = Node::<D, (), Shared>::remove(&mut (), Some(root), &key);
+                (Some(RcuSnapshot { root, len: current.len - 1 }), removed.map(into_data))
+            },
+            _ => (None, None)
+        })
+    }
+}
+
+/// moves the data out of a node that was unlinked from the current version, copying it if an older
+/// version still refers to the node
+fn into_data<D: Clone>(node: Arc<Node<D, (), Shared>>) -> D {
+    match Arc::try_unwrap(node) {
+        Ok(node) => node.data,
+        Err(node) => node.data.clone(),
+    }
+}
+
+impl<D> Default for RcuIntervalTree<D> {
+    fn default() -> RcuIntervalTree<D> { RcuIntervalTree::new() }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::cmp;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use super::RcuIntervalTree;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%500;
+        let len = rand::random::<u64>()%50;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_rcu_fuzz(){
+        let t = RcuIntervalTree::<u64>::new();
+        let mut model = BTreeMap::new();
+        let mut versions = vec![];
+        for i in 0..2000 {
+            let key = random_range();
+            if rand::random::<bool>() {
+                assert_eq!(t.insert(key, i), model.insert(key, i));
+            } else {
+                assert_eq!(t.remove(key), model.remove(&key));
+            }
+            if i % 100 == 0 { versions.push((t.load(), model.clone())) }
+        }
+        versions.push((t.load(), model));
+        for (snapshot, model) in versions {
+            assert!(snapshot.check_invariants().is_ok());
+            assert_eq!(snapshot.len(), model.len());
+            assert_eq!(snapshot.iter().map(|(k, v)| (k, *v)).collect::<Vec<_>>(), model.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>());
+            for _ in 0..20 {
+                let q = random_range();
+                let should = model.iter().filter(|&(k, _)| k.intersect(&q)).map(|(k, v)| (*k, *v)).collect::<Vec<_>>();
+                assert_eq!(snapshot.range(q.min, q.max).map(|(k, v)| (k, *v)).collect::<Vec<_>>(), should);
+                assert_eq!(snapshot.range_bounds(q.min..q.max+1).map(|(k, v)| (k, *v)).collect::<Vec<_>>(), should);
+                assert_eq!(snapshot.range_bounds(q.min..q.min).count(), 0);
+                assert_eq!(snapshot.get(q).cloned(), model.get(&q).cloned());
+            }
+        }
+    }
+
+    struct Tracked(Arc<AtomicUsize>);
+
+    impl Drop for Tracked {
+        fn drop(&mut self) { self.0.fetch_add(1, Ordering::SeqCst); }
+    }
+
+    #[test]
+    fn test_rcu_reclaims_old_versions(){
+        let drops = Arc::new(AtomicUsize::new(0));
+        let t = RcuIntervalTree::new();
+        for i in 0..10 { t.insert(memrange::Range::new(i, i), Arc::new(Tracked(drops.clone()))); }
+        let snapshot = t.load();
+        drop(t.remove(memrange::Range::new(3, 3)));
+        t.insert(memrange::Range::new(4, 4), Arc::new(Tracked(drops.clone())));
+        assert_eq!(drops.load(Ordering::SeqCst), 0);
+        assert_eq!(snapshot.len(), 10);
+        drop(snapshot);
+        assert_eq!(drops.load(Ordering::SeqCst), 2);
+        drop(t);
+        assert_eq!(drops.load(Ordering::SeqCst), 11);
+    }
+
+    #[test]
+    fn test_rcu_shares_untouched_subtrees(){
+        let t = RcuIntervalTree::new();
+        for i in 0..100 { t.insert(memrange::Range::new(i, i), i); }
+        let before = t.load();
+        t.insert(memrange::Range::new(1000, 1000), 1000);
+        let after = t.load();
+        let (old_root, new_root) = (before.root.as_ref().unwrap(), after.root.as_ref().unwrap());
+        assert!(!Arc::ptr_eq(old_root, new_root));
+        assert!(Arc::ptr_eq(old_root.left.as_ref().unwrap(), new_root.left.as_ref().unwrap()));
+        assert_eq!(before.len(), 100);
+        assert_eq!(after.len(), 101);
+    }
+
+    #[test]
+    fn test_rcu_concurrent_readers(){
+        let t = Arc::new(RcuIntervalTree::<u64>::new());
+        let readers = (0..4).map(|_| {
+            let t = t.clone();
+            thread::spawn(move || {
+                for _ in 0..2000 {
+                    // the writer only ever adds [i,i+10] after [i-1,i+9], so every version holds a prefix
+                    let snapshot = t.load();
+                    let keys = snapshot.iter().map(|(k, v)| { assert_eq!(k.min, *v); k.min }).collect::<Vec<_>>();
+                    assert_eq!(keys, (0..snapshot.len() as u64).collect::<Vec<_>>());
+                    assert_eq!(snapshot.stab(5).count(), cmp::min(snapshot.len(), 6));
+                }
+            })
+        }).collect::<Vec<_>>();
+        for i in 0..2000 { t.insert(memrange::Range::new(i, i + 10), i); }
+        for reader in readers { reader.join().expect("reader panicked") }
+    }
+}
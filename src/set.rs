@@ -0,0 +1,303 @@
+use core::iter::FromIterator;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::tree::IntervalTree;
+use ::iterators::RangePairIter;
+
+/// A set of points stored as disjoint ranges. Overlapping or adjacent ranges are merged on insert,
+/// so every point is covered by at most one stored range and two stored ranges never touch.
+#[derive(Debug)]
+pub struct IntervalSet {
+    tree: IntervalTree<()>,
+}
+
+impl IntervalSet {
+
+/// This function will construct a new empty IntervalSet.
+/// # Examples
+/// ```
+/// extern crate theban_interval_tree;
+/// let s = theban_interval_tree::IntervalSet::new();
+/// assert!(s.is_empty());
+/// ```
+    pub fn new() -> IntervalSet {
+        IntervalSet { tree: IntervalTree::new() }
+    }
+
+/// This function will add all points of range to the set. Stored ranges that overlap or are
+/// adjacent to range are merged with it.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut s = theban_interval_tree::IntervalSet::new();
+/// s.insert(Range::new(0,4));
+/// s.insert(Range::new(10,12));
+/// s.insert(Range::new(5,9));
+/// assert_eq!(s.iter().collect::<Vec<_>>(), vec![Range::new(0,12)]);
+/// ```
+    pub fn insert(&mut self, range: Range) {
+        let touching = self.tree.range(range.min.saturating_sub(1), range.max.saturating_add(1))
+            .map(|(key, _)| key).collect::<Vec<_>>();
+        let mut merged = range;
+        for key in touching {
+            merged = merged.get_union(&key);
+            self.tree.delete(key);
+        }
+        self.tree.insert(merged, ());
+    }
+
+/// This function will remove all points of range from the set, splitting stored ranges that only
+/// partially overlap it.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut s = theban_interval_tree::IntervalSet::new();
+/// s.insert(Range::new(0,10));
+/// s.remove(Range::new(4,6));
+/// assert_eq!(s.iter().collect::<Vec<_>>(), vec![Range::new(0,3), Range::new(7,10)]);
+/// ```
+    pub fn remove(&mut self, range: Range) {
+        let overlapping = self.tree.range(range.min, range.max).map(|(key, _)| key).collect::<Vec<_>>();
+        for key in overlapping {
+            self.tree.delete(key);
+            if key.min < range.min {
+                self.tree.insert(Range::new(key.min, range.min - 1), ());
+            }
+            if key.max > range.max {
+                self.tree.insert(Range::new(range.max + 1, key.max), ());
+            }
+        }
+    }
+
+/// This function will return true if the point is in the set.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut s = theban_interval_tree::IntervalSet::new();
+/// s.insert(memrange::Range::new(2,8));
+/// assert!(s.contains_point(8));
+/// assert!(!s.contains_point(9));
+/// ```
+    pub fn contains_point(&self, point: u64) -> bool {
+        self.tree.any_overlapping(point..=point)
+    }
+
+/// This function will return true if every point of range is in the set.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut s = theban_interval_tree::IntervalSet::new();
+/// s.insert(Range::new(2,8));
+/// s.insert(Range::new(10,12));
+/// assert!(s.covers(Range::new(3,8)));
+/// assert!(!s.covers(Range::new(3,10)));
+/// ```
+    pub fn covers(&self, range: Range) -> bool {
+        match self.tree.first_overlapping(range.min..=range.min) {
+            Some((key, _)) => key.max >= range.max,
+            None => false
+        }
+    }
+
+/// This function will return the number of disjoint ranges stored in the set.
+    pub fn len(&self) -> usize { self.tree.len() }
+
+/// This function will return true if the set contains no points.
+    pub fn is_empty(&self) -> bool { self.tree.is_empty() }
+
+/// This function will return the number of points in the set. The result is an u128 since the
+/// full u64 domain has 2^64 points.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut s = theban_interval_tree::IntervalSet::new();
+/// s.insert(Range::new(0,9));
+/// s.insert(Range::new(20,24));
+/// assert_eq!(s.len_covered(), 15);
+/// ```
+    pub fn len_covered(&self) -> u128 {
+        self.iter().map(|r| (r.max - r.min) as u128 + 1).sum()
+    }
+
+/// This function will return an iterator over the disjoint ranges of the set in ascending order.
+    pub fn iter(&self) -> IntervalSetIter<'_> {
+        IntervalSetIter { inner: self.tree.iter() }
+    }
+
+/// This function will return a new set containing the points that are in self or other.
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.iter().chain(other.iter()).collect()
+    }
+
+/// This function will return a new set containing the points that are in both self and other.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::IntervalSet;
+///
+/// let a = vec![Range::new(0,10), Range::new(20,30)].into_iter().collect::<IntervalSet>();
+/// let b = vec![Range::new(5,25)].into_iter().collect::<IntervalSet>();
+/// assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), vec![Range::new(5,10), Range::new(20,25)]);
+/// ```
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = IntervalSet::new();
+        let mut left = self.iter().peekable();
+        let mut right = other.iter().peekable();
+        while let (Some(&a), Some(&b)) = (left.peek(), right.peek()) {
+            if a.intersect(&b) { result.insert(a.get_intersection(&b)) }
+            if a.max < b.max { left.next(); } else { right.next(); }
+        }
+        result
+    }
+
+/// This function will return a new set containing the points that are in self but not in other.
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut result = self.iter().collect::<IntervalSet>();
+        for range in other.iter() { result.remove(range) }
+        result
+    }
+
+/// This function will return a new set containing the points of universe that are not in self.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut s = theban_interval_tree::IntervalSet::new();
+/// s.insert(Range::new(5,10));
+/// let c = s.complement(Range::new(0,20));
+/// assert_eq!(c.iter().collect::<Vec<_>>(), vec![Range::new(0,4), Range::new(11,20)]);
+/// ```
+    pub fn complement(&self, universe: Range) -> IntervalSet {
+        let mut result = IntervalSet::new();
+        let mut next = Some(universe.min);
+        for (key, _) in self.tree.range(universe.min, universe.max) {
+            if let Some(start) = next {
+                if key.min > start { result.insert(Range::new(start, key.min - 1)) }
+            }
+            next = key.max.checked_add(1);
+        }
+        if let Some(start) = next {
+            if start <= universe.max { result.insert(Range::new(start, universe.max)) }
+        }
+        result
+    }
+}
+
+impl Default for IntervalSet {
+    fn default() -> IntervalSet { IntervalSet::new() }
+}
+
+impl FromIterator<Range> for IntervalSet {
+    fn from_iter<I: IntoIterator<Item = Range>>(iter: I) -> IntervalSet {
+        let mut set = IntervalSet::new();
+        for range in iter { set.insert(range) }
+        set
+    }
+}
+
+/// Iterator over the disjoint ranges of an `IntervalSet` in ascending order.
+pub struct IntervalSetIter<'a> {
+    inner: RangePairIter<'a, ()>,
+}
+
+impl<'a> Iterator for IntervalSetIter<'a> {
+
+    type Item = Range;
+
+    fn next(&mut self) -> Option<Range> {
+        self.inner.next().map(|(key, _)| key)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use super::IntervalSet;
+
+    const UNIVERSE: u64 = 200;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%UNIVERSE;
+        let len = rand::random::<u64>()%20;
+        memrange::Range::new(offset, (offset+len).min(UNIVERSE-1))
+    }
+
+    fn bitmap(set: &IntervalSet) -> Vec<bool> {
+        (0..UNIVERSE).map(|p| set.contains_point(p)).collect()
+    }
+
+    fn check_disjoint(set: &IntervalSet) {
+        let ranges = set.iter().collect::<Vec<_>>();
+        assert_eq!(ranges.len(), set.len());
+        for pair in ranges.windows(2) {
+            assert!(pair[0].max + 1 < pair[1].min, "{:?} should be merged", pair);
+        }
+        assert_eq!(set.len_covered(), bitmap(set).iter().filter(|b| **b).count() as u128);
+    }
+
+    #[test]
+    fn test_set_fuzz(){
+        for _ in 0..50 {
+            let mut a = IntervalSet::new();
+            let mut b = IntervalSet::new();
+            let mut model = vec![false; UNIVERSE as usize];
+            for _ in 0..30 {
+                let r = random_range();
+                let insert = rand::random::<bool>();
+                if insert { a.insert(r) } else { a.remove(r) }
+                for p in r.min..r.max+1 { model[p as usize] = insert }
+                b.insert(random_range());
+                check_disjoint(&a);
+                assert_eq!(bitmap(&a), model);
+                let q = random_range();
+                assert_eq!(a.covers(q), (q.min..q.max+1).all(|p| model[p as usize]));
+            }
+            let (ba, bb) = (bitmap(&a), bitmap(&b));
+            let union = a.union(&b);
+            let intersection = a.intersection(&b);
+            let difference = a.difference(&b);
+            let complement = a.complement(memrange::Range::new(10, UNIVERSE-1));
+            for set in &[&union, &intersection, &difference, &complement] { check_disjoint(set) }
+            for p in 0..UNIVERSE as usize {
+                assert_eq!(bitmap(&union)[p], ba[p] || bb[p]);
+                assert_eq!(bitmap(&intersection)[p], ba[p] && bb[p]);
+                assert_eq!(bitmap(&difference)[p], ba[p] && !bb[p]);
+                assert_eq!(bitmap(&complement)[p], p >= 10 && !ba[p]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_domain_bounds(){
+        let max = 0xffff_ffff_ffff_ffff;
+        let mut s = IntervalSet::new();
+        s.insert(memrange::Range::new(0, max));
+        assert_eq!(s.len_covered(), 1u128 << 64);
+        s.remove(memrange::Range::new(0, 0));
+        s.remove(memrange::Range::new(max, max));
+        assert_eq!(s.iter().collect::<Vec<_>>(), vec![memrange::Range::new(1, max-1)]);
+        let c = s.complement(memrange::Range::new(0, max));
+        assert_eq!(c.iter().collect::<Vec<_>>(), vec![memrange::Range::new(0, 0), memrange::Range::new(max, max)]);
+        s.insert(memrange::Range::new(max, max));
+        assert_eq!(s.iter().collect::<Vec<_>>(), vec![memrange::Range::new(1, max)]);
+        assert!(s.complement(memrange::Range::new(1, max)).is_empty());
+    }
+}
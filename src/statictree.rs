@@ -0,0 +1,262 @@
+use core::array;
+use ::range::Range;
+use ::node::{Index, Indexed, LinkMut, Slot, search, node_height};
+use ::arena::{ArenaNode, occupy, release, root_node, extreme_pair, search_index};
+use ::error::Error;
+/// The maximal height of an AVL tree with less than 2^32 nodes (about 1.44 * log2(n)), and thus
+/// the size of the traversal stack of `StaticRangePairIter`.
+const MAX_HEIGHT: usize = 48;
+
+/// An interval tree with a fixed capacity of N entries that never allocates. All nodes are stored
+/// inline in an array and linked by u32 indices, using the same AVL operations as `IntervalTree`
+/// and `ArenaIntervalTree`. Inserting a new key into a full tree fails with
+/// `Error::CapacityExceeded` and hands the data back, and queries use a traversal stack of fixed
+/// size.
+#[derive(Debug)]
+pub struct StaticIntervalTree<D, const N: usize> {
+    slots: [Slot<ArenaNode<D>>; N],
+    free: Option<Index>,
+    root: Option<Index>,
+    len: usize,
+}
+
+impl<D, const N: usize> Default for StaticIntervalTree<D, N> {
+    fn default() -> StaticIntervalTree<D, N> {
+        StaticIntervalTree::new()
+    }
+}
+
+impl<D, const N: usize> StaticIntervalTree<D, N> {
+
+/// This function will construct a new empty StaticIntervalTree with room for N entries.
+/// # Examples
+/// ```
+/// extern crate theban_interval_tree;
+/// let t=theban_interval_tree::StaticIntervalTree::<i32, 16>::new();
+/// assert_eq!(t.capacity(), 16);
+/// ```
+    pub fn new() -> StaticIntervalTree<D, N> {
+        assert!(N < u32::MAX as usize, "StaticIntervalTree can hold at most 2^32-2 entries");
+        let slots = array::from_fn(|i| Slot::Vacant(if i + 1 < N { Some(Index::new(i + 1)) } else { None }));
+        StaticIntervalTree { slots, free: if N > 0 { Some(Index::new(0)) } else { None }, root: None, len: 0 }
+    }
+
+/// This function will insert the key,value pair into the tree, overwriting the old data if the key
+/// is allready part of the tree. If the key is new and the tree is full, `Error::CapacityExceeded`
+/// is returned together with the data, which is not stored.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+/// use theban_interval_tree::{StaticIntervalTree, Error};
+///
+/// let mut t=StaticIntervalTree::<i32, 1>::new();
+/// assert_eq!(t.insert(Range::new(2,2),25), Ok(()));
+/// assert_eq!(t.insert(Range::new(3,3),26), Err((Error::CapacityExceeded{capacity: 1}, 26)));
+/// assert_eq!(t.insert(Range::new(2,2),30), Ok(()));
+/// assert_eq!(t.get(Range::new(2,2)), Some(&30));
+/// ```
+    pub fn insert(&mut self, key: Range, data: D) -> Result<(), (Error, D)> {
+        let new = match occupy(&mut self.slots, &mut self.free, ArenaNode::new(key, data)) {
+            Ok(index) => index,
+            // no slot is left for a new node, but the data of a stored key can still be replaced
+            Err(node) => return match search_index(&self.slots, self.root, &key) {
+                Some(mut index) => { Indexed::make_mut(&mut self.slots, &mut index).data = node.data; Ok(()) },
+                None => Err((Error::CapacityExceeded { capacity: N }, node.data)),
+            }
+        };
+        let (root, unlinked) = ArenaNode::insert(&mut self.slots, self.root, new);
+        self.root = Some(root);
+        match unlinked {
+            Some(index) => { self.release(index); },
+            None => self.len += 1,
+        }
+        Ok(())
+    }
+
+    fn release(&mut self, index: Index) -> D {
+        release(&mut self.slots, &mut self.free, index).data
+    }
+
+    fn root(&self) -> Option<&ArenaNode<D>> {
+        root_node(&self.slots, &self.root)
+    }
+
+/// This function will remove the key,value pair from the tree and return the data, or None if the
+/// key is not part of the tree. The freed slot is reused by later inserts.
+    pub fn remove(&mut self, key: Range) -> Option<D> {
+        let (root, removed) = ArenaNode::remove(&mut self.slots, self.root, &key);
+        self.root = root;
+        let removed = removed?;
+        self.len -= 1;
+        Some(self.release(removed))
+    }
+
+/// This function will remove the key,value pair from the tree, doing nothing if the key is not
+/// part of the tree.
+    pub fn delete(&mut self, key: Range) {
+        self.remove(key);
+    }
+
+/// This function will return the Some(data) stored under the given key or None if the key is not
+/// known.
+    pub fn get(&self, key: Range) -> Option<&D> {
+        self.root().and_then(|root| search(&self.slots[..], &key, root))
+    }
+
+/// This function will return the data stored under the given key or the default if the key is not
+/// known.
+    pub fn get_or<'a>(&'a self, key: Range, default: &'a D) -> &'a D {
+        self.get(key).unwrap_or(default)
+    }
+
+/// This function will return true if the tree contains the given key, false otherwise
+    pub fn contains(&self, key: Range) -> bool {
+        self.get(key).is_some()
+    }
+
+/// This function will return true if the tree is empty, false otherwise.
+    pub fn empty(&self) -> bool { self.root.is_none() }
+
+/// This function will return the number of key,value pairs in the tree.
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return true if the tree is empty, false otherwise (same as `empty`).
+    pub fn is_empty(&self) -> bool { self.empty() }
+
+/// This function will return the maximal number of key,value pairs in the tree.
+    pub fn capacity(&self) -> usize { N }
+
+/// This function will return true if no new key can be inserted.
+    pub fn is_full(&self) -> bool { self.free.is_none() }
+
+/// This function will return the key/value pair with the smallest key in the tree, or None if the
+/// tree is empty.
+    pub fn min(&self) -> Option<(&Range, &D)> {
+        extreme_pair(&self.slots, self.root(), true)
+    }
+
+/// This function will return the key/value pair with the biggest key in the tree, or None if the
+/// tree is empty.
+    pub fn max(&self) -> Option<(&Range, &D)> {
+        extreme_pair(&self.slots, self.root(), false)
+    }
+
+/// This function will return the hieght of the tree. An empty tree hash height 0, one with only
+/// one elemente has height 1 etc.
+    pub fn height(&self) -> usize {
+        node_height(self.root()) as usize
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs in the tree.
+    pub fn iter(&self) -> StaticRangePairIter<'_, D> {
+        self.range(0, 0xffff_ffff_ffff_ffff)
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs between the two
+/// bounds. The iterator does not allocate.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::StaticIntervalTree::<i32, 8>::new();
+/// t.insert(memrange::Range::new(3,8),1).unwrap();
+/// t.insert(memrange::Range::new(12,36),2).unwrap();
+/// assert_eq!(t.range(9,14).map(|(k,_)| k.min).collect::<Vec<u64>>(), vec![12]);
+/// ```
+    pub fn range(&self, min: u64, max: u64) -> StaticRangePairIter<'_, D> {
+        let mut iter = StaticRangePairIter { slots: &self.slots, range: Range::new(min, max), stack: [None; MAX_HEIGHT], len: 0 };
+        iter.push_left(self.root());
+        iter
+    }
+}
+
+/// Iterator over the entries of a `StaticIntervalTree` that intersect a range. The stack holds the
+/// nodes whose left subtree was already visited, it is never deeper than the tree.
+pub struct StaticRangePairIter<'a, D: 'a> {
+    slots: &'a [Slot<ArenaNode<D>>],
+    range: Range,
+    stack: [Option<&'a ArenaNode<D>>; MAX_HEIGHT],
+    len: usize,
+}
+
+impl<'a, D: 'a> StaticRangePairIter<'a, D> {
+    fn push_left(&mut self, mut next: Option<&'a ArenaNode<D>>) {
+        while let Some(n) = next {
+            if !n.left_subtree_relevant(&self.range) { return }
+            self.stack[self.len] = Some(n);
+            self.len += 1;
+            next = n.successors(self.slots).0;
+        }
+    }
+}
+
+impl<'a, D: 'a> Iterator for StaticRangePairIter<'a, D> {
+
+    type Item = (Range, &'a D);
+
+    fn next(&mut self) -> Option<(Range, &'a D)> {
+        while self.len > 0 {
+            self.len -= 1;
+            let n = self.stack[self.len].take()?;
+            // every remaining node starts at or after this one
+            if n.key.min > self.range.max { self.len = 0; return None }
+            self.push_left(n.successors(self.slots).1);
+            if n.key.intersect(&self.range) { return Some((n.key, &n.data)) }
+        }
+        None
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::collections::BTreeMap;
+    use super::StaticIntervalTree;
+    use node::check_invariants;
+    use error::Error;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%50;
+        let len = rand::random::<u64>()%50;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_static_fuzz(){
+        let mut t = StaticIntervalTree::<u64, 100>::new();
+        let mut model = BTreeMap::new();
+        for i in 1..5000 {
+            let range = random_range();
+            if rand::random::<u8>() < 160 {
+                let fits = model.len() < 100 || model.contains_key(&range);
+                if fits {
+                    assert_eq!(t.insert(range, i), Ok(()));
+                    model.insert(range, i);
+                } else {
+                    assert_eq!(t.insert(range, i), Err((Error::CapacityExceeded { capacity: 100 }, i)));
+                }
+            } else {
+                assert_eq!(t.remove(range), model.remove(&range));
+            }
+            assert_eq!(t.len(), model.len());
+            assert_eq!(t.is_full(), model.len() == 100);
+            assert_eq!(check_invariants(&t.slots[..], t.root()), Ok(()));
+            let q = random_range();
+            let should = model.iter().filter(|&(k, _)| k.intersect(&q)).map(|(k, v)| (*k, v)).collect::<Vec<_>>();
+            assert_eq!(t.range(q.min, q.max).collect::<Vec<_>>(), should);
+        }
+        assert_eq!(t.min(), model.iter().next());
+        assert_eq!(t.max(), model.iter().next_back());
+    }
+
+    #[test]
+    fn test_zero_capacity(){
+        let mut t = StaticIntervalTree::<u64, 0>::new();
+        assert_eq!(t.insert(memrange::Range::new(1, 2), 3), Err((Error::CapacityExceeded { capacity: 0 }, 3)));
+        assert!(t.empty());
+        assert_eq!(t.iter().count(), 0);
+    }
+}
@@ -0,0 +1,194 @@
+use core::ops::RangeBounds;
+use alloc::boxed::Box;
+use ::range::Range;
+use ::node::Node;
+use ::tree::IntervalTree;
+use ::observer::TreeObserver;
+use ::bounds::closed_range;
+
+/// A monoid that summarizes the entries of a subtree, e.g. their total weight or minimal priority.
+/// Every node of an `IntervalTree<D, S>` caches the summary of its subtree, which is kept up to
+/// date through inserts, deletes and rotations. `combine` has to be associative and `identity`
+/// has to be neutral for it. Summaries are always combined in key order, so `combine` does not
+/// need to be commutative.
+pub trait Summary<D>: Clone {
+    /// The summary of no entries at all.
+    fn identity() -> Self;
+    /// The summary of a single entry.
+    fn from_entry(key: &Range, data: &D) -> Self;
+    /// The summary of the entries summarized by `self`, followed by those summarized by `other`.
+    fn combine(&self, other: &Self) -> Self;
+}
+
+/// The summary used by trees that don't need one, it costs no space and no time.
+impl<D> Summary<D> for () {
+    fn identity() {}
+    fn from_entry(_key: &Range, _data: &D) {}
+    fn combine(&self, _other: &()) {}
+}
+
+fn concat<D, S: Summary<D>>(left: S, right: S) -> S {
+    left.combine(&right)
+}
+
+/// the summary of all entries below node that intersect range
+fn aggregate_overlapping<D, S: Summary<D>>(node: &Option<Box<Node<D, S>>>, range: &Range) -> S {
+    let node = match *node {
+        Some(ref node) => node,
+        None => return S::identity()
+    };
+    let mut result = S::identity();
+    if node.left_subtree_relevant(range) {
+        result = aggregate_overlapping(&node.left, range);
+    }
+    if node.key.intersect(range) {
+        result = concat(result, S::from_entry(&node.key, &node.data));
+    }
+    if node.right_subtree_relevant(range) {
+        result = concat(result, aggregate_overlapping(&node.right, range));
+    }
+    result
+}
+
+/// the summary of all entries below node whose key lies between the (inclusive) bounds lower and
+/// upper. Once a node between both bounds is found, each of its subtrees only has a single bound
+/// left, so only two paths are walked and whole subtrees are taken from their cached summary.
+fn aggregate_between<D, S: Summary<D>>(node: &Option<Box<Node<D, S>>>, lower: Option<&Range>, upper: Option<&Range>) -> S {
+    let node = match *node {
+        Some(ref node) => node,
+        None => return S::identity()
+    };
+    if lower.is_none() && upper.is_none() { return node.summary.clone() }
+    if lower.is_some_and(|l| node.key < *l) { return aggregate_between(&node.right, lower, upper) }
+    if upper.is_some_and(|u| node.key > *u) { return aggregate_between(&node.left, lower, upper) }
+    let left = aggregate_between(&node.left, lower, None);
+    let own = S::from_entry(&node.key, &node.data);
+    concat(concat(left, own), aggregate_between(&node.right, None, upper))
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will construct a new empty IntervalTree that maintains the summary S for every
+/// subtree.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use theban_interval_tree::{IntervalTree, Summary};
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct TotalWeight(u64);
+///
+/// impl Summary<u64> for TotalWeight {
+///     fn identity() -> TotalWeight { TotalWeight(0) }
+///     fn from_entry(_key: &memrange::Range, weight: &u64) -> TotalWeight { TotalWeight(*weight) }
+///     fn combine(&self, other: &TotalWeight) -> TotalWeight { TotalWeight(self.0 + other.0) }
+/// }
+///
+/// let mut t = IntervalTree::<u64, TotalWeight>::with_summary();
+/// t.insert(memrange::Range::new(0,9), 3);
+/// t.insert(memrange::Range::new(5,20), 4);
+/// t.insert(memrange::Range::new(30,40), 5);
+/// assert_eq!(t.summary(), TotalWeight(12));
+/// assert_eq!(t.aggregate(8..=25), TotalWeight(7));
+/// assert_eq!(t.aggregate_starting_in(5..=40), TotalWeight(9));
+/// ```
+    pub fn with_summary() -> IntervalTree<D, S, O> {
+        IntervalTree::from_root(None, 0)
+    }
+
+/// This function will return the summary of all entries in the tree in O(1).
+    pub fn summary(&self) -> S {
+        self.root.as_ref().map_or_else(S::identity, |root| root.summary.clone())
+    }
+
+/// This function will return the combined summary of all entries whose key intersects the given
+/// bounds, in key order. Subtrees that can't intersect the bounds are pruned like in `range`, but
+/// every intersecting entry is visited.
+    pub fn aggregate<R: RangeBounds<u64>>(&self, bounds: R) -> S {
+        match closed_range(&bounds) {
+            Some(range) => aggregate_overlapping(&self.root, &range),
+            None => S::identity()
+        }
+    }
+
+/// This function will return the combined summary of all entries whose key starts within the
+/// given bounds, in key order. Since these entries are consecutive in the tree, this only takes
+/// O(log(n)).
+    pub fn aggregate_starting_in<R: RangeBounds<u64>>(&self, bounds: R) -> S {
+        match closed_range(&bounds) {
+            Some(range) => aggregate_between(&self.root, Some(&Range::new(range.min, range.min)),
+                Some(&Range::new(range.max, 0xffff_ffff_ffff_ffff))),
+            None => S::identity()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::collections::BTreeMap;
+    use super::Summary;
+    use node::is_interval_tree;
+
+    /// number of entries, sum of their data and the keys in order
+    #[derive(Clone, Debug, PartialEq)]
+    struct Stats(u64, u64, Vec<memrange::Range>);
+
+    impl Summary<u64> for Stats {
+        fn identity() -> Stats { Stats(0, 0, vec![]) }
+        fn from_entry(key: &memrange::Range, data: &u64) -> Stats { Stats(1, *data, vec![*key]) }
+        fn combine(&self, other: &Stats) -> Stats {
+            let mut keys = self.2.clone();
+            keys.extend(other.2.iter().cloned());
+            Stats(self.0 + other.0, self.1 + other.1, keys)
+        }
+    }
+
+    fn brute_force<'a, I: Iterator<Item=(&'a memrange::Range, &'a u64)>>(entries: I) -> Stats {
+        entries.fold(Stats::identity(), |acc, (k, v)| acc.combine(&Stats::from_entry(k, v)))
+    }
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%100;
+        let len = rand::random::<u64>()%20;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_summary_fuzz(){
+        let mut t = ::IntervalTree::<u64, Stats>::with_summary();
+        let mut model = BTreeMap::new();
+        for i in 1..3000 {
+            let range = random_range();
+            if rand::random::<bool>() {
+                t.insert(range, i);
+                model.insert(range, i);
+            } else {
+                t.delete(range);
+                model.remove(&range);
+            }
+            assert!(is_interval_tree(&t.root));
+            assert_eq!(t.summary(), brute_force(model.iter()));
+            let q = random_range();
+            assert_eq!(t.aggregate(q.min..=q.max), brute_force(model.iter().filter(|&(k,_)| k.intersect(&q))));
+            assert_eq!(t.aggregate_starting_in(q.min..=q.max),
+                brute_force(model.iter().filter(|&(k,_)| q.min <= k.min && k.min <= q.max)));
+        }
+    }
+
+    #[test]
+    fn test_check_summaries(){
+        let mut t = ::IntervalTree::<u64, Stats>::with_summary();
+        for i in 0..20 {
+            t.insert(memrange::Range::new(i, i+5), i);
+        }
+        assert_eq!(t.check_invariants(), Ok(()));
+        let key = {
+            let root = t.root.as_mut().unwrap();
+            root.summary.1 += 1;
+            root.key
+        };
+        assert_eq!(t.check_invariants(), Err(::InvariantError::WrongSummary(key)));
+    }
+}
@@ -0,0 +1,183 @@
+use core::cmp::{Ordering, Reverse};
+use core::ops::RangeBounds;
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use ::range::Range;
+use ::node::Node;
+use ::tree::IntervalTree;
+use ::observer::TreeObserver;
+use ::summary::Summary;
+use ::bounds::closed_range;
+
+/// An element of the best-first search in `range_top_k_by_len`: either a subtree whose keys are at
+/// most `span+1` long, or a single entry whose key is exactly `span+1` long. For equal spans subtrees
+/// come before entries, so that all entries of that length are known before the first one is
+/// returned, and smaller keys before bigger ones.
+struct Candidate<'a, D: 'a, S: 'a> {
+    span: u64,
+    subtree: bool,
+    node: &'a Node<D, S>,
+}
+
+impl<'a, D: 'a, S: 'a> Candidate<'a, D, S> {
+    fn rank(&self) -> (u64, bool, Reverse<Range>) {
+        (self.span, self.subtree, Reverse(self.node.key))
+    }
+}
+
+impl<'a, D: 'a, S: 'a> PartialEq for Candidate<'a, D, S> {
+    fn eq(&self, other: &Self) -> bool { self.rank() == other.rank() }
+}
+
+impl<'a, D: 'a, S: 'a> Eq for Candidate<'a, D, S> {}
+
+impl<'a, D: 'a, S: 'a> PartialOrd for Candidate<'a, D, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl<'a, D: 'a, S: 'a> Ord for Candidate<'a, D, S> {
+    fn cmp(&self, other: &Self) -> Ordering { self.rank().cmp(&other.rank()) }
+}
+
+/// An entry scored by the user in `range_top_k_by`. Entries with equal score are ranked by their
+/// position in the tree, earlier entries being better.
+struct Scored<'a, O, D: 'a> {
+    score: O,
+    position: usize,
+    key: Range,
+    data: &'a D,
+}
+
+impl<'a, O: Ord, D: 'a> Scored<'a, O, D> {
+    fn rank(&self) -> (&O, Reverse<usize>) {
+        (&self.score, Reverse(self.position))
+    }
+}
+
+impl<'a, O: Ord, D: 'a> PartialEq for Scored<'a, O, D> {
+    fn eq(&self, other: &Self) -> bool { self.rank() == other.rank() }
+}
+
+impl<'a, O: Ord, D: 'a> Eq for Scored<'a, O, D> {}
+
+impl<'a, O: Ord, D: 'a> PartialOrd for Scored<'a, O, D> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl<'a, O: Ord, D: 'a> Ord for Scored<'a, O, D> {
+    fn cmp(&self, other: &Self) -> Ordering { self.rank().cmp(&other.rank()) }
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will return the k longest keys intersecting the given bounds together with their
+/// data, longest first (keys of equal length in key order). Every node caches the maximal length
+/// of the keys in its subtree, so subtrees are only visited while they could still contain one of
+/// the k longest keys.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(0,10),1);
+/// t.insert(Range::new(5,6),2);
+/// t.insert(Range::new(8,30),3);
+/// t.insert(Range::new(40,100),4);
+/// assert_eq!(t.range_top_k_by_len(5..=9, 2), vec![(Range::new(8,30),&3), (Range::new(0,10),&1)]);
+/// ```
+    pub fn range_top_k_by_len<R: RangeBounds<u64>>(&self, bounds: R, k: usize) -> Vec<(Range, &D)> {
+        let range = match closed_range(&bounds) {
+            Some(range) => range,
+            None => return Vec::new()
+        };
+        let mut result = Vec::with_capacity(k);
+        let mut heap = BinaryHeap::new();
+        if let Some(ref root) = self.root {
+            heap.push(Candidate { span: root.max_span(), subtree: true, node: root });
+        }
+        while result.len() < k {
+            let candidate = match heap.pop() {
+                Some(candidate) => candidate,
+                None => break
+            };
+            let node = candidate.node;
+            if !candidate.subtree {
+                result.push((node.key, &node.data));
+                continue
+            }
+            if node.key.intersect(&range) {
+                heap.push(Candidate { span: node.key.max-node.key.min, subtree: false, node });
+            }
+            if node.left_subtree_relevant(&range) {
+                if let Some(ref left) = node.left { heap.push(Candidate { span: left.max_span(), subtree: true, node: left }) }
+            }
+            if node.right_subtree_relevant(&range) {
+                if let Some(ref right) = node.right { heap.push(Candidate { span: right.max_span(), subtree: true, node: right }) }
+            }
+        }
+        result
+    }
+
+/// This function will return the k entries intersecting the given bounds with the highest score,
+/// best first (entries with equal score in key order). The score can be any function of the key
+/// and the data, so every intersecting entry is scored, but only k of them are kept in memory at
+/// any time.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(0,10),1);
+/// t.insert(Range::new(5,6),7);
+/// t.insert(Range::new(8,30),3);
+/// assert_eq!(t.range_top_k_by(5..=9, 2, |_, data| *data), vec![(Range::new(5,6),&7), (Range::new(8,30),&3)]);
+/// ```
+    pub fn range_top_k_by<R, T, F>(&self, bounds: R, k: usize, mut score: F) -> Vec<(Range, &D)>
+        where R: RangeBounds<u64>, T: Ord, F: FnMut(&Range, &D) -> T {
+        if k == 0 { return Vec::new() }
+        let mut heap = BinaryHeap::with_capacity(k+1);
+        for (position, (key, data)) in self.range_bounds(bounds).enumerate() {
+            heap.push(Reverse(Scored { score: score(&key, data), position, key, data }));
+            if heap.len() > k { heap.pop(); }
+        }
+        heap.into_sorted_vec().into_iter().map(|Reverse(scored)| (scored.key, scored.data)).collect()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::cmp::Reverse;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%1000;
+        let len = rand::random::<u64>()%100;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_top_k_fuzz(){
+        let mut t = ::IntervalTree::<u64>::new();
+        for i in 0..500 {
+            t.insert(random_range(), i%7);
+        }
+        assert!(t.check_invariants().is_ok());
+        for _ in 0..100 {
+            let q = random_range();
+            let k = rand::random::<usize>()%20;
+            let mut all = t.range(q.min, q.max).collect::<Vec<_>>();
+            all.sort_by_key(|&(key,_)| Reverse(key.max-key.min));
+            all.truncate(k);
+            assert_eq!(t.range_top_k_by_len(q.min..=q.max, k), all);
+
+            let mut all = t.range(q.min, q.max).collect::<Vec<_>>();
+            all.sort_by(|a, b| b.1.cmp(a.1));
+            all.truncate(k);
+            assert_eq!(t.range_top_k_by(q.min..=q.max, k, |_, data| *data), all);
+        }
+    }
+}
@@ -1,11 +1,24 @@
+use core::fmt;
+use core::ops::RangeBounds;
 use node::Node;
-use memrange::Range;
-use node::{insert,delete,search,min_pair, max_pair, height};
-use iterators::RangePairIter;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use range::Range;
+use node::{insert,remove,search,min_pair, max_pair, height, drop_iteratively, check_invariants, NodeRef, InvariantError};
+use iterators::{RangePairIter, ClippedRangePairIter};
+use error::{Error, OnDuplicate, check_interval};
+use summary::Summary;
+use bounds::{closed_range, bound_values};
+use observer::{Observers, TreeObserver};
 
-#[derive(Debug)]
-pub struct IntervalTree<D> {
-    pub root: Option<Box<Node<D>>>
+/// An interval tree mapping `Range` keys to data of type `D`. Every subtree additionally caches a
+/// summary `S` of its entries (see `Summary`), trees created by `new` use the empty summary `()`.
+/// `O` is the type of the observers the tree notifies about its changes (see `TreeObserver`). By
+/// default they have to be `Send + Sync`, so that the tree can be shared between threads.
+pub struct IntervalTree<D, S = (), O: ?Sized = dyn TreeObserver<D> + Send + Sync> {
+    pub(crate) root: Option<Box<Node<D, S>>>,
+    pub(crate) len: usize,
+    pub(crate) observers: Observers<O>,
 }
 
 impl <D> IntervalTree<D>{
@@ -18,8 +31,22 @@ impl <D> IntervalTree<D>{
 /// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
 /// ```
     pub fn new() -> IntervalTree<D>{
-        IntervalTree{root: None}
+        IntervalTree::from_root(None, 0)
     }
+}
+
+impl<D, S, O: ?Sized> IntervalTree<D, S, O> {
+    /// wraps root, which holds len entries, into a tree without observers
+    pub(crate) fn from_root(root: Option<Box<Node<D, S>>>, len: usize) -> IntervalTree<D, S, O> {
+        IntervalTree{root, len, observers: Vec::new()}
+    }
+}
+
+impl<D, S: Summary<D>, O: ?Sized> Default for IntervalTree<D, S, O> {
+    fn default() -> IntervalTree<D, S, O> { IntervalTree::from_root(None, 0) }
+}
+
+impl <D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O>{
 
 /// This function will insert the key,value pair into the tree, overwriting the old data if the key is allready
 /// part of the tree.
@@ -35,10 +62,48 @@ impl <D> IntervalTree<D>{
 /// assert_eq!(t.get(memrange::Range::new(2,2)), Some(&30));
 /// ```
     pub fn insert(&mut self, key: Range, data: D) {
-        match self.root.take() {
-            Some(box_to_node) => self.root = Some(insert::<D>(key, data, box_to_node)),
-            None => self.root = Some(Box::new(Node::new(key,data))),
+        let (root, old) = match self.root.take() {
+            Some(box_to_node) => insert(key, data, box_to_node),
+            None => (Box::new(Node::new(key,data)), None),
+        };
+        if old.is_none() { self.len += 1 }
+        if !self.observers.is_empty() {
+            let new = search(&(), &key, &root).expect("key was just inserted");
+            for observer in self.observers.iter_mut() {
+                match old {
+                    Some(ref old) => observer.on_replace(key, old, new),
+                    None => observer.on_insert(key, new),
+                }
+            }
         }
+        self.root = Some(root);
+    }
+
+/// This function will insert the key,value pair into the tree like `insert`, but checks the key
+/// first: inverted keys (`min > max`, which can be built with a struct literal) are rejected with
+/// `Error::InvertedInterval`, and if `on_duplicate` is `OnDuplicate::Reject` an existing key is
+/// reported as `Error::DuplicateKey` without touching the old data.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use theban_interval_tree::{Error, OnDuplicate};
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// assert_eq!(t.try_insert(memrange::Range::new(2,2), 25, OnDuplicate::Reject), Ok(()));
+/// assert_eq!(t.try_insert(memrange::Range::new(2,2), 30, OnDuplicate::Reject),
+///     Err(Error::DuplicateKey(memrange::Range::new(2,2))));
+/// assert_eq!(t.try_insert(memrange::Range{min: 5, max: 3}, 30, OnDuplicate::Overwrite),
+///     Err(Error::InvertedInterval{min: 5, max: 3}));
+/// assert_eq!(t.get(memrange::Range::new(2,2)), Some(&25));
+/// ```
+    pub fn try_insert(&mut self, key: Range, data: D, on_duplicate: OnDuplicate) -> Result<(), Error> {
+        check_interval(key.min, key.max)?;
+        if on_duplicate == OnDuplicate::Reject && self.contains(key) {
+            return Err(Error::DuplicateKey(key))
+        }
+        self.insert(key, data);
+        Ok(())
     }
 
 /// This function will remove the key,value pair from the tree, doing nothing if the key is not
@@ -57,10 +122,32 @@ impl <D> IntervalTree<D>{
 /// assert!(t.empty());
 /// ```
     pub fn delete(&mut self, key: Range){
-        match self.root.take() {
-            Some(box_to_node) => self.root = delete(key,box_to_node),
-            None => return
+        self.remove(key);
+    }
+
+/// This function will remove the key,value pair from the tree and return the data, or None if the
+/// key is not part of the tree.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(2,2),25);
+/// assert_eq!(t.remove(memrange::Range::new(2,2)), Some(25));
+/// assert_eq!(t.remove(memrange::Range::new(2,2)), None);
+/// ```
+    pub fn remove(&mut self, key: Range) -> Option<D> {
+        let (root, data) = match self.root.take() {
+            Some(box_to_node) => remove(key, box_to_node),
+            None => return None
+        };
+        self.root = root;
+        if let Some(ref data) = data {
+            self.len -= 1;
+            for observer in self.observers.iter_mut() { observer.on_remove(key, data) }
         }
+        data
     }
 
 /// This function will return the Some(data) stored under the given key or None if the key is not
@@ -78,7 +165,7 @@ impl <D> IntervalTree<D>{
 /// ```
     pub fn get(&self, key: Range) -> Option<&D>{
         match self.root {
-            Some(ref box_to_node) =>search(&key, box_to_node),
+            Some(ref box_to_node) =>search(&(), &key, box_to_node),
             None => None
         }
     }
@@ -130,6 +217,25 @@ impl <D> IntervalTree<D>{
 /// ```
     pub fn empty(&self) -> bool { self.root.is_none() }
 
+/// This function will return the number of key,value pairs in the tree in O(1).
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(2,2),25);
+/// t.insert(memrange::Range::new(2,2),30);
+/// t.insert(memrange::Range::new(3,8),35);
+/// assert_eq!(t.len(), 2);
+/// t.delete(memrange::Range::new(2,2));
+/// assert_eq!(t.len(), 1);
+/// ```
+    pub fn len(&self) -> usize { self.len }
+
+/// This function will return true if the tree is empty, false otherwise. Same as `empty`.
+    pub fn is_empty(&self) -> bool { self.root.is_none() }
+
 /// This function will return the key/value pair with the smallest key in the tree, or None if the
 /// tree is empty.
 /// # Examples
@@ -198,7 +304,7 @@ impl <D> IntervalTree<D>{
 /// }
 ///
 /// ```
-    pub fn iter(&self) -> RangePairIter<D>{
+    pub fn iter(&self) -> RangePairIter<'_, D, S>{
         RangePairIter::new(self, 0, 0xffff_ffff_ffff_ffff)
     }
 
@@ -213,16 +319,109 @@ impl <D> IntervalTree<D>{
 /// }
 ///
 /// ```
-    pub fn range(&self, min: u64, max: u64) -> RangePairIter<D>{
+    pub fn range(&self, min: u64, max: u64) -> RangePairIter<'_, D, S>{
         RangePairIter::new(self, min, max)
     }
 
+/// This function will return a read only iterator for all (key,value) pairs intersecting the
+/// given bounds like `range_bounds`, but every pair additionally comes with its key clipped to the
+/// bounds.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(2,8),1);
+/// for (key,clipped,val) in t.range_clipped(5..=100) {
+///     assert_eq!((key,clipped,*val), (Range::new(2,8),Range::new(5,8),1));
+/// }
+/// ```
+    pub fn range_clipped<R: RangeBounds<u64>>(&self, bounds: R) -> ClippedRangePairIter<'_, D, S>{
+        match closed_range(&bounds) {
+            Some(range) => ClippedRangePairIter::new(self, range.min, range.max),
+            None => ClippedRangePairIter::empty(),
+        }
+    }
+
+/// This function will return a read only iterator for all (key,value) pairs intersecting the
+/// given bounds like `range_bounds`, but returns `Error::InvertedInterval` instead of an empty
+/// iterator if the start bound is bigger than the end bound. Bounds like `5..5` are empty but not
+/// inverted.
+/// # Examples
+/// ```
+/// extern crate theban_interval_tree;
+/// use std::ops::Bound;
+/// use theban_interval_tree::Error;
+///
+/// let t=theban_interval_tree::IntervalTree::<i32>::new();
+/// assert_eq!(t.try_range(9..=100).map(|iter| iter.count()), Ok(0));
+/// assert_eq!(t.try_range(9..9).map(|iter| iter.count()), Ok(0));
+/// assert_eq!(t.try_range((Bound::Included(100), Bound::Excluded(9))).err(),
+///     Some(Error::InvertedInterval{min: 100, max: 9}));
+/// ```
+    pub fn try_range<R: RangeBounds<u64>>(&self, bounds: R) -> Result<RangePairIter<'_, D, S>, Error>{
+        let (min, max) = bound_values(&bounds);
+        check_interval(min, max)?;
+        Ok(self.range_bounds(bounds))
+    }
+
+/// This function will return a read only view of the root node, or None if the tree is empty. It
+/// can be used to walk the structure of the tree, e.g. to visualize it.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(2,2),25);
+/// t.insert(memrange::Range::new(3,9),50);
+/// let root = t.root().unwrap();
+/// assert_eq!(root.key(), &memrange::Range::new(2,2));
+/// assert_eq!(root.subtree_max(), 9);
+/// assert_eq!(root.right().unwrap().data(), &50);
+///
+/// ```
+    pub fn root(&self) -> Option<NodeRef<'_, D, S>> {
+        self.root.as_ref().map(|n| NodeRef::new(n))
+    }
+
+/// This function will check that every node of the tree is ordered, balanced and caches the
+/// correct height, subtree max and summary, returning the first violation found. Summaries are
+/// compared with `==`, so this needs a summary type that implements `PartialEq`.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(2,2),25);
+/// assert_eq!(t.check_invariants(), Ok(()));
+///
+/// ```
+    pub fn check_invariants(&self) -> Result<(), InvariantError> where S: PartialEq {
+        check_invariants(&(), self.root.as_deref())
+    }
+
+}
+
+impl<D: fmt::Debug, S: fmt::Debug, O: ?Sized> fmt::Debug for IntervalTree<D, S, O> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IntervalTree").field("root", &self.root).field("observers", &self.observers.len()).finish()
+    }
 }
 
-#[cfg(test)]
+impl<D, S, O: ?Sized> Drop for IntervalTree<D, S, O> {
+    fn drop(&mut self) {
+        drop_iteratively(self.root.take())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use {memrange, rand};
-    use node::is_interval_tree;
+    use node::{is_interval_tree, Node};
 
     fn random_range() -> memrange::Range {
         let offset = rand::random::<u64>()%50;
@@ -249,4 +448,32 @@ mod tests {
         };
         return;
     }
+
+    #[test]
+    fn test_drop_deep_chain(){
+        let mut t = ::IntervalTree::<i32>::new();
+        let mut chain = None;
+        for i in 0..1_000_000 {
+            let mut node = Box::new(Node::new(memrange::Range::new(i, i), 0));
+            node.left = chain;
+            chain = Some(node);
+        }
+        t.root = chain;
+        t.len = 1_000_000;
+        drop(t);
+    }
+
+    #[test]
+    fn test_check_invariants(){
+        let mut t = ::IntervalTree::<i32>::new();
+        for i in 0..20 {
+            t.insert(memrange::Range::new(i, i+5), 0);
+        }
+        assert_eq!(t.check_invariants(), Ok(()));
+        {
+            let left = t.root.as_mut().unwrap().left.as_mut().unwrap();
+            left.right.as_mut().unwrap().key = memrange::Range::new(100, 100);
+        }
+        assert_eq!(t.check_invariants(), Err(::InvariantError::Unsorted(memrange::Range::new(100, 100))));
+    }
 }
@@ -0,0 +1,152 @@
+use core::ops::{ControlFlow, RangeBounds};
+use ::range::Range;
+use ::node::Node;
+use ::tree::IntervalTree;
+use ::observer::TreeObserver;
+use ::summary::Summary;
+use ::bounds::closed_range;
+
+/// calls f for every key below node that intersects range, in key order, until f breaks. Only the
+/// left successors are visited recursively, the right ones in a loop, so this uses no heap and at
+/// most height stack frames.
+fn visit_overlapping<'a, D, S, B, F>(node: &'a Node<D, S>, range: &Range, f: &mut F)
+    -> ControlFlow<B> where F: FnMut(Range, &'a D) -> ControlFlow<B> {
+    let mut current = Some(node);
+    while let Some(node) = current {
+        if node.left_subtree_relevant(range) {
+            if let Some(ref left) = node.left { visit_overlapping(left, range, f)?; }
+        }
+        if node.key.intersect(range) { f(node.key, &node.data)?; }
+        current = if node.right_subtree_relevant(range) { node.right.as_deref() } else { None };
+    }
+    ControlFlow::Continue(())
+}
+
+impl<D, S: Summary<D>, O: ?Sized + TreeObserver<D>> IntervalTree<D, S, O> {
+
+/// This function will call f for every (key,value) pair intersecting the given bounds in key
+/// order, like iterating over `range_bounds`, but without allocating. As soon as f returns
+/// `ControlFlow::Break`, the walk stops and the break value is returned.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+/// use std::ops::ControlFlow;
+/// use memrange::Range;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(Range::new(0,10),1);
+/// t.insert(Range::new(5,20),2);
+/// t.insert(Range::new(8,9),3);
+/// let mut sum = 0;
+/// let found = t.for_each_overlapping(6..=8, |_, v| {
+///     sum += *v;
+///     if sum > 2 { ControlFlow::Break(*v) } else { ControlFlow::Continue(()) }
+/// });
+/// assert_eq!(found, ControlFlow::Break(2));
+/// ```
+    pub fn for_each_overlapping<'a, R, B, F>(&'a self, bounds: R, mut f: F) -> ControlFlow<B>
+        where R: RangeBounds<u64>, F: FnMut(Range, &'a D) -> ControlFlow<B> {
+        match (&self.root, closed_range(&bounds)) {
+            (Some(root), Some(range)) => visit_overlapping(root, &range, &mut f),
+            _ => ControlFlow::Continue(())
+        }
+    }
+
+/// This function will return true if any key intersects the given bounds.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(0,10),1);
+/// assert!(t.any_overlapping(10..=12));
+/// assert!(!t.any_overlapping(11..=12));
+/// ```
+    pub fn any_overlapping<R: RangeBounds<u64>>(&self, bounds: R) -> bool {
+        self.for_each_overlapping(bounds, |_, _| ControlFlow::Break(())).is_break()
+    }
+
+/// This function will return the (key,value) pair with the smallest key that intersects the given
+/// bounds, or None.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(0,10),1);
+/// t.insert(memrange::Range::new(5,20),2);
+/// assert_eq!(t.first_overlapping(12..=30), Some((memrange::Range::new(5,20), &2)));
+/// ```
+    pub fn first_overlapping<R: RangeBounds<u64>>(&self, bounds: R) -> Option<(Range, &D)> {
+        match self.for_each_overlapping(bounds, |key, data| ControlFlow::Break((key, data))) {
+            ControlFlow::Break(pair) => Some(pair),
+            ControlFlow::Continue(()) => None
+        }
+    }
+
+/// This function will return the number of keys that intersect the given bounds.
+/// # Examples
+/// ```
+/// extern crate memrange;
+/// extern crate theban_interval_tree;
+///
+/// let mut t=theban_interval_tree::IntervalTree::<i32>::new();
+/// t.insert(memrange::Range::new(0,10),1);
+/// t.insert(memrange::Range::new(5,20),2);
+/// assert_eq!(t.count_overlapping(8..=9), 2);
+/// ```
+    pub fn count_overlapping<R: RangeBounds<u64>>(&self, bounds: R) -> usize {
+        let mut count = 0;
+        let _ = self.for_each_overlapping::<R, (), _>(bounds, |_, _| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use {memrange, rand};
+    use std::ops::ControlFlow;
+
+    fn random_range() -> memrange::Range {
+        let offset = rand::random::<u64>()%1000;
+        let len = rand::random::<u64>()%100;
+        memrange::Range::new(offset, offset+len)
+    }
+
+    #[test]
+    fn test_visit_fuzz(){
+        let mut t = ::IntervalTree::<u64>::new();
+        for i in 0..500 {
+            t.insert(random_range(), i);
+        }
+        for _ in 0..200 {
+            let q = random_range();
+            let all = t.range(q.min, q.max).collect::<Vec<_>>();
+            let mut visited = vec![];
+            let _ = t.for_each_overlapping::<_, (), _>(q.min..=q.max, |k, v| {
+                visited.push((k, v));
+                ControlFlow::Continue(())
+            });
+            assert_eq!(visited, all);
+            assert_eq!(t.count_overlapping(q.min..=q.max), all.len());
+            assert_eq!(t.any_overlapping(q.min..=q.max), !all.is_empty());
+            assert_eq!(t.first_overlapping(q.min..=q.max), all.first().cloned());
+            let limit = rand::random::<usize>()%10;
+            let mut seen = 0;
+            let stopped = t.for_each_overlapping(q.min..=q.max, |k, _| {
+                seen += 1;
+                if seen > limit { ControlFlow::Break(k) } else { ControlFlow::Continue(()) }
+            });
+            match all.get(limit) {
+                Some(&(k, _)) => assert_eq!(stopped, ControlFlow::Break(k)),
+                None => assert_eq!(stopped, ControlFlow::Continue(()))
+            }
+        }
+    }
+}
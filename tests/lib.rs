@@ -193,3 +193,67 @@ fn test_range_iter_nontrivial(){
     assert_eq!(should, is);
     };
 }
+
+#[test]
+fn test_node_refs(){
+    let mut t = theban_interval_tree::IntervalTree::<i32>::new();
+    for _ in 1..500 {
+        t.insert(random_range(), 1337);
+    }
+    assert_eq!(t.check_invariants(), Ok(()));
+    let mut keys = vec![];
+    let mut stack = t.root().into_iter().collect::<Vec<_>>();
+    while let Some(node) = stack.pop() {
+        keys.push(*node.key());
+        assert!(node.height() <= t.height());
+        assert!(node.subtree_max() >= node.key().max);
+        stack.extend(node.left());
+        stack.extend(node.right());
+    }
+    keys.sort();
+    assert_eq!(keys, t.iter().map(|(k,_)| k).collect::<Vec<Range>>());
+}
+
+#[test]
+fn test_range_bounds(){
+    use std::ops::Bound;
+    let mut t = theban_interval_tree::IntervalTree::<i32>::new();
+    t.insert_bounds(0..10, 1).unwrap();
+    t.insert_bounds(10..20, 2).unwrap();
+    t.insert_bounds((Bound::Excluded(20), Bound::Included(30)), 3).unwrap();
+    t.insert_bounds(40.., 4).unwrap();
+    assert_eq!(t.insert_bounds(50..50, 5), Err(5));
+    assert_eq!(t.range_bounds(..10).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1]);
+    assert_eq!(t.range_bounds(9..11).map(|(_,v)| *v).collect::<Vec<_>>(), vec![1,2]);
+    assert_eq!(t.range_bounds((Bound::Excluded(18), Bound::Excluded(21))).map(|(_,v)| *v).collect::<Vec<_>>(), vec![2]);
+    assert_eq!(t.range_bounds((Bound::Excluded(19), Bound::Excluded(21))).count(), 0);
+    assert_eq!(t.range_bounds(30..).map(|(_,v)| *v).collect::<Vec<_>>(), vec![3,4]);
+    assert_eq!(t.range_bounds((Bound::Excluded(!0), Bound::Unbounded)).count(), 0);
+    assert_eq!(t.range_bounds(..).count(), 4);
+    assert_eq!(t.count_overlapping(..), 4);
+    assert_eq!(t.count_overlapping(19..22), 2);
+    assert_eq!(t.first_overlapping(25..), Some((Range::new(21,30), &3)));
+    assert!(!t.any_overlapping(31..40));
+    assert_eq!(t.range_clipped(5..15).map(|(_,c,_)| c).collect::<Vec<_>>(), vec![Range::new(5,9), Range::new(10,14)]);
+    assert_eq!(t.range_clipped(15..15).count(), 0);
+    assert_eq!(t.depth_profile(38..42), vec![(Range::new(38,39),0), (Range::new(40,41),1)]);
+    assert_eq!(t.depth_profile(15..15), vec![]);
+    assert_eq!(t.range_top_k_by_len(..=20, 1), vec![(Range::new(0,9), &1)]);
+}
+
+#[test]
+fn test_try_insert(){
+    use theban_interval_tree::{Error, OnDuplicate, InvariantError};
+    let mut t = theban_interval_tree::IntervalTree::<i32>::new();
+    assert_eq!(t.try_insert(Range::new(1,5), 1, OnDuplicate::Reject), Ok(()));
+    assert_eq!(t.try_insert(Range::new(1,5), 2, OnDuplicate::Reject), Err(Error::DuplicateKey(Range::new(1,5))));
+    assert_eq!(t.get(Range::new(1,5)), Some(&1));
+    assert_eq!(t.try_insert(Range::new(1,5), 2, OnDuplicate::Overwrite), Ok(()));
+    assert_eq!(t.get(Range::new(1,5)), Some(&2));
+    assert_eq!(t.try_insert(Range{min: 9, max: 8}, 3, OnDuplicate::Overwrite), Err(Error::InvertedInterval{min: 9, max: 8}));
+    assert!(!t.contains(Range{min: 9, max: 8}));
+    assert_eq!(t.check_invariants().map_err(Error::from), Ok(()));
+    assert_eq!(Error::from(InvariantError::Unbalanced(Range::new(1,5))), Error::InvariantViolation(InvariantError::Unbalanced(Range::new(1,5))));
+    assert!(t.try_range((std::ops::Bound::Included(3), std::ops::Bound::Included(2))).is_err());
+    assert_eq!(t.try_range(2..=3).expect("valid range").count(), 1);
+}